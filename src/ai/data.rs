@@ -1,6 +1,10 @@
 use crate::{
-    Direction, Game, Pos, Tile,
-    engine::moves::{Move, PlayedTile},
+    Direction, Gaddag, Game, Pos, Tile,
+    engine::{
+        moves::{Move, PlayedTile},
+        words::formed_words,
+    },
+    game::{alphabet::Alphabet, board::Board},
 };
 use csv::Reader;
 use memmap2::{Mmap, MmapOptions};
@@ -9,6 +13,7 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{BufWriter, Write},
+    sync::Arc,
 };
 
 #[derive(Clone, Debug)]
@@ -25,11 +30,27 @@ pub struct GameMove {
     pub equity: f32,
 }
 
+pub use crate::engine::actions::Action;
+
+// a play whose words are judged differently by two lexica
 #[derive(Clone, Debug)]
-pub enum Action {
-    Move(Move),
-    Swap(Vec<Tile>),
-    Pass,
+pub struct RevalidatedPlay {
+    pub game_id: String,
+    pub turn: usize, // index into GameRecord::moves
+    pub player: usize,
+    pub words: Vec<String>,
+    pub old_phonies: Vec<String>,
+    pub new_phonies: Vec<String>,
+}
+
+impl RevalidatedPlay {
+    pub fn became_phony(&self) -> bool {
+        self.old_phonies.is_empty() && !self.new_phonies.is_empty()
+    }
+
+    pub fn newly_valid(&self) -> bool {
+        !self.old_phonies.is_empty() && self.new_phonies.is_empty()
+    }
 }
 
 // Macondo self-play format
@@ -46,12 +67,34 @@ pub struct CsvRow {
     pub equity: f32,
 }
 
+// tile counts in positions and network inputs are indexed by Tile::to_index: the blank at 0, then the letters.
+// fixed at 27 so the file layout and the network's input size don't change, which fits alphabets of up to 26 letters
+pub const LETTER_TYPES: usize = 27;
+
+pub fn check_alphabet(alphabet: &Alphabet) -> Result<(), String> {
+    if alphabet.len() + 1 > LETTER_TYPES {
+        return Err(format!(
+            "the {} alphabet has {} letters, training positions and the network only fit {}",
+            alphabet.name,
+            alphabet.len(),
+            LETTER_TYPES - 1
+        ));
+    }
+    Ok(())
+}
+
+// positions file: magic (8) | format version (u32) | letter types (u32), little endian, then TrainingPositions back to back.
+// version 1 was the headerless layout from before alphabets, with the blank counted at 26 and the bag shifted down one
+const MAGIC: &[u8; 8] = b"SCRABPOS";
+const FORMAT_VERSION: u32 = 2;
+const HEADER_LEN: usize = 16;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct TrainingPosition {
-    pub board: [[u8; 15]; 15], // 225 bytes, board tiles (0=empty, 1-27=tiles)
-    pub rack_counts: [u8; 27], // player rack counts
-    pub bag_counts: [u8; 27],  // unseen tiles
+    pub board: [[u8; 15]; 15],           // 225 bytes, board tiles (0=empty, else Tile::to_index + 1)
+    pub rack_counts: [u8; LETTER_TYPES], // player rack counts
+    pub bag_counts: [u8; LETTER_TYPES],  // unseen tiles
     pub my_score: u16,
     pub opp_score: u16,
     pub scoreless_turns: u8,
@@ -60,7 +103,7 @@ pub struct TrainingPosition {
 }
 
 impl GameRecord {
-    pub fn from_csv(csv_content: &str) -> Result<Vec<GameRecord>, Box<dyn std::error::Error>> {
+    pub fn from_csv(csv_content: &str, alphabet: &Alphabet) -> Result<Vec<GameRecord>, Box<dyn std::error::Error>> {
         let mut reader = Reader::from_reader(csv_content.as_bytes());
         let mut games: HashMap<String, Vec<CsvRow>> = HashMap::new();
 
@@ -77,39 +120,25 @@ impl GameRecord {
                 .into_iter()
                 .map(|row| {
                     let player = if row.player_id == "p1" { 0 } else { 1 };
-                    let rack = row
-                        .rack
-                        .chars()
-                        .map(|c| match c {
-                            '?' => Tile::blank(None),
-                            c => Tile::letter(c as u8),
-                        })
-                        .collect();
+                    let rack = alphabet.parse_tiles(&row.rack).ok_or_else(|| format!("invalid rack {}", row.rack))?;
 
                     let action = match row.play.trim() {
                         "(Pass)" => Action::Pass,
                         s if s.starts_with("(exch ") => {
                             let tiles_str = &s[6..s.len() - 1];
-                            let tiles = tiles_str
-                                .chars()
-                                .map(|c| match c {
-                                    '?' => Tile::blank(None),
-                                    c => Tile::letter(c as u8),
-                                })
-                                .collect();
-                            Action::Swap(tiles)
+                            Action::Swap(alphabet.parse_tiles(tiles_str).ok_or_else(|| format!("invalid exchange {}", s))?)
                         }
-                        _ => Action::Move(parse_move(&row.play, row.score).unwrap()),
+                        _ => Action::Move(parse_move(&row.play, row.score, alphabet)?),
                     };
 
-                    GameMove {
+                    Ok(GameMove {
                         player,
                         action,
                         rack,
                         equity: row.equity,
-                    }
+                    })
                 })
-                .collect();
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
 
             records.push(GameRecord { game_id, moves });
         }
@@ -117,21 +146,67 @@ impl GameRecord {
         Ok(records)
     }
 
-    pub fn csv_to_positions(csv_path: &str, output_path: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    /// Replays the game and checks every formed word against both lexica.
+    /// Returns the plays where the two disagree: new phonies, plays that became valid, or a changed set of phonies.
+    pub fn revalidate(&self, old: &Gaddag, new: &Gaddag) -> Vec<RevalidatedPlay> {
+        let mut board = Board::new();
+        let mut changed = Vec::new();
+
+        for (turn, game_move) in self.moves.iter().enumerate() {
+            let Action::Move(mv) = &game_move.action else {
+                continue;
+            };
+
+            let words = formed_words(&board, mv);
+            let phonies = |gaddag: &Gaddag| -> Vec<String> {
+                words
+                    .iter()
+                    .filter(|word| !gaddag.contains(&word.bytes()))
+                    .map(|word| word.get_word_string(gaddag.alphabet()))
+                    .collect()
+            };
+            let old_phonies = phonies(old);
+            let new_phonies = phonies(new);
+            if old_phonies != new_phonies {
+                changed.push(RevalidatedPlay {
+                    game_id: self.game_id.clone(),
+                    turn,
+                    player: game_move.player,
+                    words: words.iter().map(|word| word.get_word_string(new.alphabet())).collect(),
+                    old_phonies,
+                    new_phonies,
+                });
+            }
+
+            for (pos, played_tile) in mv.tile_positions() {
+                if let PlayedTile::Rack(tile) = played_tile {
+                    board.place_tile(pos, tile);
+                }
+            }
+        }
+
+        changed
+    }
+
+    pub fn csv_to_positions(csv_path: &str, output_path: &str, gaddag: Arc<Gaddag>) -> Result<usize, Box<dyn std::error::Error>> {
+        check_alphabet(gaddag.alphabet())?;
         let csv_content = std::fs::read_to_string(csv_path)?;
-        let records = Self::from_csv(&csv_content)?;
+        let records = Self::from_csv(&csv_content, gaddag.alphabet())?;
 
         let mut writer = BufWriter::new(File::create(output_path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(LETTER_TYPES as u32).to_le_bytes())?;
         let mut position_count = 0;
 
         for record in records {
-            let mut game = Game::init();
+            let mut game = Game::init(gaddag.clone());
 
             for game_move in &record.moves {
                 let mut pos = TrainingPosition {
                     board: [[0; 15]; 15],
-                    rack_counts: [0; 27],
-                    bag_counts: [0; 27],
+                    rack_counts: [0; LETTER_TYPES],
+                    bag_counts: [0; LETTER_TYPES],
                     my_score: game.scores[game.current_player],
                     opp_score: game.scores[1 - game.current_player],
                     scoreless_turns: game.zeroed_turns,
@@ -139,19 +214,15 @@ impl GameRecord {
                     target_equity: game_move.equity,
                 };
 
-                for row in 0..15 {
-                    for col in 0..15 {
-                        if let Some(tile) = game.board.get_board_tile(Pos::new(row, col)) {
-                            pos.board[row][col] = tile.to_index() + 1; // 0=empty, 1-27=tiles
-                        }
-                    }
+                for (tile_pos, tile) in game.board.tiles() {
+                    pos.board[tile_pos.row][tile_pos.col] = tile.to_index() + 1; // 0=empty
                 }
 
                 for tile in game.racks[game.current_player].tiles() {
                     pos.rack_counts[tile.to_index() as usize] += 1;
                 }
 
-                for i in 0..27 {
+                for i in 0..LETTER_TYPES {
                     pos.bag_counts[i] = game.bag.count(i);
                 }
                 for tile in game.racks[1 - game.current_player].tiles() {
@@ -161,11 +232,7 @@ impl GameRecord {
                 let bytes = unsafe { std::slice::from_raw_parts(&pos as *const _ as *const u8, std::mem::size_of::<TrainingPosition>()) };
                 writer.write_all(bytes)?;
 
-                match &game_move.action {
-                    Action::Move(mv) => game.play_move(mv),
-                    Action::Pass => game.pass_turn(),
-                    Action::Swap(tiles) => game.exchange(tiles.clone()),
-                }
+                game.play_action(&game_move.action);
 
                 position_count += 1;
             }
@@ -177,8 +244,8 @@ impl GameRecord {
     }
 }
 
-pub fn parse_move(play_str: &str, score: u16) -> Result<Move, Box<dyn std::error::Error>> {
-    let parts: Vec<&str> = play_str.trim().split_whitespace().collect();
+pub fn parse_move(play_str: &str, score: u16, alphabet: &Alphabet) -> Result<Move, Box<dyn std::error::Error>> {
+    let parts: Vec<&str> = play_str.split_whitespace().collect();
     let pos_str = parts[0];
     let word = parts[1];
 
@@ -195,14 +262,20 @@ pub fn parse_move(play_str: &str, score: u16) -> Result<Move, Box<dyn std::error
         (Pos::new(row, col), Direction::Vertical)
     };
 
-    let tiles_data: SmallVec<[PlayedTile; 7]> = word
-        .chars()
-        .map(|c| match c {
-            '.' => PlayedTile::Board(Tile::letter(b'.')),
-            c if c.is_lowercase() => PlayedTile::Rack(Tile::blank(Some(c.to_ascii_uppercase() as u8))),
-            c => PlayedTile::Rack(Tile::letter(c as u8)),
-        })
-        .collect();
+    // uppercase letters from the rack, lowercase for blanks, . for a tile already on the board
+    let chars: Vec<char> = word.chars().collect();
+    let mut tiles_data: SmallVec<[PlayedTile; 7]> = SmallVec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '.' {
+            tiles_data.push(PlayedTile::Board(Tile::empty())); // the letter is whatever is on the board
+            i += 1;
+            continue;
+        }
+        let (letter, len, lowercase) = alphabet.match_letter(&chars, i).ok_or_else(|| format!("invalid letter in {}", word))?;
+        tiles_data.push(PlayedTile::Rack(if lowercase { Tile::blank(Some(letter)) } else { Tile::letter(letter) }));
+        i += len;
+    }
 
     Ok(Move {
         tiles_data,
@@ -221,10 +294,25 @@ impl PositionsReader {
     pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let file = File::open(path)?;
         let file_len = file.metadata()?.len() as usize;
+        if file_len < HEADER_LEN {
+            return Err(format!("{} is too short for a positions file", path).into());
+        }
         let position_size = std::mem::size_of::<TrainingPosition>();
-        let count = file_len / position_size;
+        let count = (file_len - HEADER_LEN) / position_size;
 
         let mmap = unsafe { MmapOptions::new().map(&file)? };
+        if &mmap[..8] != MAGIC {
+            return Err(format!(
+                "{} is not a positions file, or one written before format version {}",
+                path, FORMAT_VERSION
+            )
+            .into());
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into().unwrap());
+        let letter_types = u32::from_le_bytes(mmap[12..16].try_into().unwrap()) as usize;
+        if version != FORMAT_VERSION || letter_types != LETTER_TYPES {
+            return Err(format!("unsupported positions format version {} with {} letter types", version, letter_types).into());
+        }
 
         Ok(PositionsReader { mmap, count })
     }
@@ -233,19 +321,64 @@ impl PositionsReader {
         self.count
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
     pub fn get(&self, index: usize) -> Option<&TrainingPosition> {
         if index >= self.count {
             return None;
         }
 
         unsafe {
-            let positions = std::slice::from_raw_parts(self.mmap.as_ptr() as *const TrainingPosition, self.count);
+            let positions = std::slice::from_raw_parts(self.mmap[HEADER_LEN..].as_ptr() as *const TrainingPosition, self.count);
             Some(&positions[index])
         }
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &TrainingPosition> {
-        let positions = unsafe { std::slice::from_raw_parts(self.mmap.as_ptr() as *const TrainingPosition, self.count) };
+        let positions = unsafe { std::slice::from_raw_parts(self.mmap[HEADER_LEN..].as_ptr() as *const TrainingPosition, self.count) };
         positions.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(words: &[&str]) -> Gaddag {
+        Gaddag::from_bytes(words.join("\n").as_bytes(), Alphabet::english()).unwrap()
+    }
+
+    #[test]
+    fn revalidates_plays_against_two_lexica() {
+        // CAT, then CATS, a pass, ZA down onto the A and QS down onto the S
+        let csv = "playerID,gameID,turn,rack,play,score,equity\n\
+                   p1,g1,0,ACTQ,8H CAT,10,0\n\
+                   p2,g1,1,SZ,8H ...S,6,0\n\
+                   p1,g1,2,Q,(Pass),0,0\n\
+                   p2,g1,3,Z,I7 Z.,11,0\n\
+                   p1,g1,4,Q,K7 Q.,11,0\n";
+        let records = GameRecord::from_csv(csv, &Alphabet::english()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].moves.len(), 5);
+
+        // CATS is gone from the new lexicon and ZA came in. QS is a phony in both, so it isn't reported
+        let old = lexicon(&["AT", "CAT", "CATS"]);
+        let new = lexicon(&["AT", "CAT", "ZA"]);
+        let changed = records[0].revalidate(&old, &new);
+        assert_eq!(changed.iter().map(|play| play.turn).collect::<Vec<_>>(), [1, 3]);
+
+        assert_eq!(changed[0].player, 1);
+        assert_eq!(changed[0].words, ["CATS"]);
+        assert!(changed[0].old_phonies.is_empty());
+        assert_eq!(changed[0].new_phonies, ["CATS"]);
+        assert!(changed[0].became_phony() && !changed[0].newly_valid());
+
+        assert_eq!(changed[1].words, ["ZA"]);
+        assert_eq!(changed[1].old_phonies, ["ZA"]);
+        assert!(changed[1].newly_valid() && !changed[1].became_phony());
+
+        assert!(records[0].revalidate(&new, &new).is_empty());
+    }
+}
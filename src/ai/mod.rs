@@ -16,16 +16,28 @@ output:
 - scalar for how good this action is
 */
 
+// data only reads and writes positions, everything that touches candle is behind the ml feature
 pub mod data;
+#[cfg(feature = "ml")]
 pub mod network;
+#[cfg(feature = "ml")]
+pub mod player;
+#[cfg(feature = "ml")]
 pub mod training;
 
+#[cfg(feature = "ml")]
+use crate::BOARD_SIZE;
+#[cfg(feature = "ml")]
 use crate::game::Game;
-use crate::{BOARD_SIZE, Pos};
+#[cfg(feature = "ml")]
 use candle_core::{Device, Result, Tensor};
 
+#[cfg(feature = "ml")]
+use data::{LETTER_TYPES, check_alphabet};
+#[cfg(feature = "ml")]
 use network::{BOARD_CHANNELS, FEATURES};
 
+#[cfg(feature = "ml")]
 pub fn games_to_tensors(device: &Device, games: &[Game]) -> Result<(Tensor, Tensor)> {
     let batch_size = games.len();
 
@@ -47,14 +59,13 @@ pub fn games_to_tensors(device: &Device, games: &[Game]) -> Result<(Tensor, Tens
 }
 
 // TODO OPPONENT TILES, DRY UP
+#[cfg(feature = "ml")]
 pub fn game_to_tensors(device: &Device, game: &Game) -> Result<(Tensor, Tensor)> {
+    check_alphabet(game.gaddag.alphabet()).map_err(candle_core::Error::Msg)?;
+
     let mut board_data = vec![0f32; BOARD_SIZE * BOARD_SIZE];
-    for row in 0..BOARD_SIZE {
-        for col in 0..BOARD_SIZE {
-            if let Some(tile) = game.board.get_board_tile(Pos::new(row, col)) {
-                board_data[row * BOARD_SIZE + col] = (tile.to_index() as f32 + 1.0) / 28.0;
-            }
-        }
+    for (pos, tile) in game.board.tiles() {
+        board_data[pos.row * BOARD_SIZE + pos.col] = (tile.to_index() as f32 + 1.0) / 28.0;
     }
 
     // global data
@@ -67,16 +78,16 @@ pub fn game_to_tensors(device: &Device, game: &Game) -> Result<(Tensor, Tensor)>
     }
 
     for (tile, count) in game.bag.get_tile_counts() {
-        global_data[27 + tile.to_index() as usize] = count as f32 / 12.0;
+        global_data[LETTER_TYPES + tile.to_index() as usize] = count as f32 / 12.0;
     }
 
     // score gap -> global
     let my_score = game.scores[game.current_player] as f32;
     let opp_score = game.scores[1 - game.current_player] as f32;
-    global_data[54] = ((my_score - opp_score) / 100.0).tanh();
+    global_data[2 * LETTER_TYPES] = ((my_score - opp_score) / 100.0).tanh();
 
     // scoreless turns -> global
-    global_data[55] = game.zeroed_turns as f32 / 6.0;
+    global_data[2 * LETTER_TYPES + 1] = game.zeroed_turns as f32 / 6.0;
 
     let board_tensor = Tensor::from_vec(board_data, &[1, BOARD_CHANNELS, BOARD_SIZE, BOARD_SIZE], device)?;
     let global_tensor = Tensor::from_vec(global_data, &[1, FEATURES], device)?;
@@ -1,3 +1,4 @@
+pub use super::data::LETTER_TYPES;
 use crate::BOARD_SIZE;
 use candle_core::{D, DType, Device, Result, Tensor};
 use candle_nn::{BatchNorm, BatchNormConfig, Conv2d, Conv2dConfig, Linear, ModuleT, VarBuilder, VarMap, batch_norm, conv2d, linear};
@@ -6,13 +7,22 @@ use candle_nn::{BatchNorm, BatchNormConfig, Conv2d, Conv2dConfig, Linear, Module
 const NUM_FILTERS: usize = 64;
 const NUM_BLOCKS: usize = 6;
 
-pub const LETTER_TYPES: usize = 27; // 26 letters + 1 blank
 const RACK_SIZE: usize = LETTER_TYPES; // counts of each letter in rack
 const BAG_SIZE: usize = LETTER_TYPES; // counts of each letter in bag
 
 pub const BOARD_CHANNELS: usize = 1;
 pub const FEATURES: usize = RACK_SIZE + BAG_SIZE + 1 + 1;
 
+// the first gpu with the cuda feature, the cpu without it or when there's no gpu
+pub fn default_device() -> Device {
+    if cfg!(feature = "cuda")
+        && let Ok(device) = Device::new_cuda(0)
+    {
+        return device;
+    }
+    Device::Cpu
+}
+
 pub struct Network {
     pub device: Device,
     board_conv: Conv2d,
@@ -26,22 +36,21 @@ pub struct Network {
 }
 
 impl Network {
-    pub fn init() -> Result<Self> {
-        Network::new(VarMap::new())
+    pub fn init(device: Device) -> Result<Self> {
+        Network::new(VarMap::new(), device)
     }
 
     pub fn save(&self, path: &str) -> Result<()> {
         self.varmap.save(path)
     }
 
-    pub fn load(path: &str) -> Result<Self> {
+    pub fn load(path: &str, device: Device) -> Result<Self> {
         let mut varmap = VarMap::new();
         varmap.load(path)?;
-        Self::new(varmap)
+        Self::new(varmap, device)
     }
 
-    pub fn new(varmap: VarMap) -> Result<Self> {
-        let device = Device::new_cuda(0).unwrap_or(Device::Cpu);
+    pub fn new(varmap: VarMap, device: Device) -> Result<Self> {
         let vb = VarBuilder::from_varmap(&varmap, DType::F32, &device);
 
         // board
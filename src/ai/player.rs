@@ -0,0 +1,67 @@
+use super::{games_to_tensors, network::Network};
+use crate::{
+    engine::{actions::Action, moves::MoveGenerator},
+    game::Game,
+};
+use candle_core::Result;
+
+// positions go through the network this many at a time
+const BATCH_SIZE: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct RatedAction {
+    pub action: Action,
+    pub value: f32,
+}
+
+/// The position the network rates an action by, still from the mover's side:
+/// the move's tiles on the board and off the rack before drawing, exchanged tiles off the rack, or nothing for a pass.
+pub fn position_after(game: &Game, action: &Action) -> Game {
+    match action {
+        Action::Move(mv) => game.simulate_move(mv),
+        Action::Swap(tiles) => {
+            let mut position = game.clone();
+            for &tile in tiles {
+                position.racks[position.current_player].remove_tile(tile);
+            }
+            position.zeroed_turns += 1;
+            position
+        }
+        Action::Pass => {
+            let mut position = game.clone();
+            position.zeroed_turns += 1;
+            position
+        }
+    }
+}
+
+impl Network {
+    /// Every legal action for the player to move with the network's value for it, best first.
+    pub fn rate_actions(&self, game: &Game) -> Result<Vec<RatedAction>> {
+        if game.is_over() {
+            return Ok(Vec::new());
+        }
+        let generator = MoveGenerator {
+            board: game.board.clone(),
+            rack: game.racks[game.current_player].clone(),
+        };
+        let actions = generator.generate_actions(&game.bag);
+
+        let mut rated = Vec::with_capacity(actions.len());
+        for batch in actions.chunks(BATCH_SIZE) {
+            let positions: Vec<Game> = batch.iter().map(|action| position_after(game, action)).collect();
+            let (board, global) = games_to_tensors(&self.device, &positions)?;
+            // one position squeezes down to a scalar
+            let values = self.forward(&board, &global, false)?.flatten_all()?.to_vec1::<f32>()?;
+            rated.extend(batch.iter().cloned().zip(values).map(|(action, value)| RatedAction { action, value }));
+        }
+
+        rated.sort_by(|a, b| b.value.total_cmp(&a.value));
+        Ok(rated)
+    }
+
+    // the action the network rates highest, pass when there's nothing to rate
+    pub fn choose_action(&self, game: &Game) -> Result<Action> {
+        Ok(self.rate_actions(game)?.into_iter().next().map_or(Action::Pass, |rated| rated.action))
+    }
+}
@@ -1,5 +1,5 @@
 use crate::ai::data::PositionsReader;
-use crate::ai::network::Network;
+use crate::ai::network::{FEATURES, Network};
 use candle_core::Tensor;
 use candle_nn::{AdamW, Optimizer, ParamsAdamW, loss};
 use rand::{Rng, prelude::SliceRandom};
@@ -41,7 +41,7 @@ pub fn train(network: &mut Network, positions_path: &str) -> Result<(), Box<dyn
 
         for batch_indices in train_indices.chunks(BATCH_SIZE) {
             let mut board_data = Vec::with_capacity(batch_indices.len() * 225);
-            let mut global_data = Vec::with_capacity(batch_indices.len() * 56);
+            let mut global_data = Vec::with_capacity(batch_indices.len() * FEATURES);
             let mut targets = Vec::with_capacity(batch_indices.len());
 
             for &idx in batch_indices {
@@ -70,17 +70,17 @@ pub fn train(network: &mut Network, positions_path: &str) -> Result<(), Box<dyn
             }
 
             let board_tensor = Tensor::from_vec(board_data, &[batch_indices.len(), 1, 15, 15], &network.device)?;
-            let global_tensor = Tensor::from_vec(global_data, &[batch_indices.len(), 56], &network.device)?;
+            let global_tensor = Tensor::from_vec(global_data, &[batch_indices.len(), FEATURES], &network.device)?;
             train_batch(network, &mut optimizer, &board_tensor, &global_tensor, &targets)?;
         }
         if epoch % 10 == 0 {
-            network.save(&format!("models/checkpoint.safetensors"))?;
+            network.save("models/checkpoint.safetensors")?;
         }
         println!("Epoch {} complete", epoch);
         let lr = 0.001 * 0.95_f64.powi(epoch as i32); // decay
         optimizer.set_learning_rate(lr);
     }
 
-    network.save(&format!("models/model.safetensors"))?;
+    network.save("models/model.safetensors")?;
     Ok(())
 }
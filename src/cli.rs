@@ -0,0 +1,129 @@
+use scrab::{
+    Lexicons,
+    ai::data::GameRecord,
+    engine::{
+        diff,
+        probability::{DrawOdds, study_lists},
+        superleaves,
+    },
+    game::bag::Bag,
+};
+use std::collections::BTreeSet;
+
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+// command line tools, `scrab <command> ...`. None if the args aren't a command, so main falls through
+pub fn run(args: &[String]) -> Option<CliResult> {
+    let (command, args) = args.split_first()?;
+    match command.as_str() {
+        "diff" => Some(lexicon_diff(args)),
+        "study" => Some(study(args)),
+        "leaves" => Some(leaves(args)),
+        _ => None,
+    }
+}
+
+// scrab diff <old lexicon> <new lexicon> [games.csv]
+// lexica are names in wordlists/, like CSW21 and CSW24
+fn lexicon_diff(args: &[String]) -> CliResult {
+    let [old_name, new_name, rest @ ..] = args else {
+        return Err("usage: scrab diff <old lexicon> <new lexicon> [games.csv]".into());
+    };
+
+    let mut lexicons = Lexicons::default();
+    let old = lexicons.load(old_name)?;
+    let new = lexicons.load(new_name)?;
+    let lexicon_diff = diff::diff(&old, &new)?;
+
+    println!(
+        "{} -> {}: {} added, {} removed",
+        old_name,
+        new_name,
+        lexicon_diff.added_count(),
+        lexicon_diff.removed_count()
+    );
+    let lengths: BTreeSet<usize> = lexicon_diff.added.keys().chain(lexicon_diff.removed.keys()).copied().collect();
+    for len in lengths {
+        let added = lexicon_diff.added.get(&len).map_or(0, Vec::len);
+        let removed = lexicon_diff.removed.get(&len).map_or(0, Vec::len);
+        println!("{:>2} letters: +{} -{}", len, added, removed);
+    }
+    for (len, words) in &lexicon_diff.added {
+        println!("\nadded, {} letters:\n{}", len, words.join(" "));
+    }
+    for (len, words) in &lexicon_diff.removed {
+        println!("\nremoved, {} letters:\n{}", len, words.join(" "));
+    }
+
+    if let Some(csv_path) = rest.first() {
+        let records = GameRecord::from_csv(&std::fs::read_to_string(csv_path)?, new.alphabet())?;
+        println!("\nrevalidating {} games from {}", records.len(), csv_path);
+        for record in &records {
+            for play in record.revalidate(&old, &new) {
+                let status = if play.became_phony() {
+                    "now phony"
+                } else if play.newly_valid() {
+                    "now valid"
+                } else {
+                    "phonies changed"
+                };
+                println!(
+                    "{} turn {} (p{}): {} - {} [{} -> {}]",
+                    play.game_id,
+                    play.turn + 1,
+                    play.player + 1,
+                    play.words.join(", "),
+                    status,
+                    play.old_phonies.join(", "),
+                    play.new_phonies.join(", ")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// scrab study <lexicon> <length> [count]
+// probability ordered alphagrams with their anagrams, for the full bag of the lexicon's alphabet
+fn study(args: &[String]) -> CliResult {
+    let [name, length, rest @ ..] = args else {
+        return Err("usage: scrab study <lexicon> <length> [count]".into());
+    };
+    let length: usize = length.parse()?;
+    let count: usize = match rest.first() {
+        Some(count) => count.parse()?,
+        None => usize::MAX,
+    };
+
+    let gaddag = Lexicons::default().load(name)?;
+    let odds = DrawOdds::new(&Bag::new(gaddag.alphabet()));
+    let lists = study_lists(&gaddag, &odds, length..=length);
+    for entry in lists.get(&length).into_iter().flatten().take(count) {
+        println!(
+            "{:>6} {} {} ({:.6}%)",
+            entry.rank,
+            entry.alphagram,
+            entry.words.join(" "),
+            entry.probability * 100.0
+        );
+    }
+    Ok(())
+}
+
+// scrab leaves <lexicon> <out.csv> [rounds] [games per round]
+// superleaves from self-play, written as a csv LeaveTable::load reads
+fn leaves(args: &[String]) -> CliResult {
+    let [name, out_path, rest @ ..] = args else {
+        return Err("usage: scrab leaves <lexicon> <out.csv> [rounds] [games per round]".into());
+    };
+    let rounds: usize = rest.first().map_or(Ok(3), |rounds| rounds.parse())?;
+    let games: usize = rest.get(1).map_or(Ok(10_000), |games| games.parse())?;
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    let gaddag = Lexicons::default().load(name)?;
+    let table = superleaves::generate(&gaddag, rounds, games, threads, 20.0);
+    table.save_csv(out_path, gaddag.alphabet())?;
+    println!("{} leaves from {} rounds of {} games -> {}", table.len(), rounds, games, out_path);
+    Ok(())
+}
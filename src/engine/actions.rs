@@ -0,0 +1,120 @@
+use super::moves::{Move, MoveGenerator};
+use crate::game::{
+    alphabet::MAX_TILE_TYPES,
+    bag::{Bag, MIN_BAG_FOR_EXCHANGE},
+    rack::Rack,
+    tile::Tile,
+};
+
+// everything a player can do on their turn
+#[derive(Clone, Debug)]
+pub enum Action {
+    Move(Move),
+    Swap(Vec<Tile>),
+    Pass,
+}
+
+/// Every distinct set of rack tiles that can be exchanged, ignoring order:
+/// a rack with two Es gives one exchange of a single E, and blanks are all the same tile.
+/// Smallest exchanges first, tiles in `Tile::to_index` order (blanks first).
+pub fn exchanges(rack: &Rack) -> Vec<Vec<Tile>> {
+    let mut counts = [0u8; MAX_TILE_TYPES];
+    for tile in rack.tiles() {
+        counts[tile.to_index() as usize] += 1;
+    }
+    let kinds: Vec<(Tile, u8)> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(idx, &count)| (Tile::from_index(idx as u8), count))
+        .collect();
+
+    // odometer over how many of each kind go back in the bag
+    let mut exchanges = Vec::new();
+    let mut taken = vec![0u8; kinds.len()];
+    loop {
+        let mut i = 0;
+        while i < kinds.len() && taken[i] == kinds[i].1 {
+            taken[i] = 0;
+            i += 1;
+        }
+        if i == kinds.len() {
+            break;
+        }
+        taken[i] += 1;
+
+        let exchange: Vec<Tile> = kinds
+            .iter()
+            .zip(&taken)
+            .flat_map(|(&(tile, _), &n)| std::iter::repeat_n(tile, n as usize))
+            .collect();
+        exchanges.push(exchange);
+    }
+
+    exchanges.sort_by_key(|exchange| exchange.len());
+    exchanges
+}
+
+impl MoveGenerator {
+    /// All placements (in `generate_moves` order), then every distinct exchange if the bag allows one, then pass.
+    pub fn generate_actions(&self, bag: &Bag) -> Vec<Action> {
+        let mut actions: Vec<Action> = self.generate_moves().into_iter().map(Action::Move).collect();
+        if bag.len() >= MIN_BAG_FOR_EXCHANGE {
+            actions.extend(exchanges(&self.rack).into_iter().map(Action::Swap));
+        }
+        actions.push(Action::Pass);
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::{
+            anchors::AnchoredBoard,
+            testing::{gaddag, rack},
+        },
+        game::{alphabet::Alphabet, board::Board},
+    };
+
+    #[test]
+    fn every_distinct_exchange() {
+        // 2^7 - 1 subsets of seven different tiles
+        assert_eq!(exchanges(&rack("AENORST")).len(), 127);
+
+        // 0-2 As, 0-1 B and 0-2 blanks, less keeping everything
+        let exchanges = exchanges(&rack("AAB??"));
+        assert_eq!(exchanges.len(), 3 * 2 * 3 - 1);
+        let mut sorted = exchanges.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), exchanges.len());
+        assert!(exchanges.windows(2).all(|pair| pair[0].len() <= pair[1].len()));
+        assert_eq!(exchanges.last().unwrap(), &rack("??AAB").tiles().to_vec());
+    }
+
+    #[test]
+    fn swaps_need_a_full_rack_in_the_bag() {
+        let generator = MoveGenerator {
+            board: AnchoredBoard::new(Board::new(), gaddag()),
+            rack: rack("AENORST"),
+        };
+        let swaps = |bag: &Bag| {
+            let actions = generator.generate_actions(bag);
+            assert!(matches!(actions.last(), Some(Action::Pass)));
+            assert_eq!(
+                actions.iter().filter(|action| matches!(action, Action::Move(_))).count(),
+                generator.count_moves()
+            );
+            actions.iter().filter(|action| matches!(action, Action::Swap(_))).count()
+        };
+
+        let mut bag = Bag::new(&Alphabet::english());
+        assert_eq!(swaps(&bag), 127);
+        while bag.len() >= MIN_BAG_FOR_EXCHANGE {
+            bag.draw();
+        }
+        assert_eq!(swaps(&bag), 0);
+    }
+}
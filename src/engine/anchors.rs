@@ -1,108 +1,248 @@
+use super::gaddag::Gaddag;
 use crate::{
-    Direction, GADDAG, Pos,
-    game::board::{BOARD_SIZE, Board},
+    Direction, Pos,
+    game::{
+        alphabet::MAX_LETTERS,
+        board::{BOARD_SIZE, Board, mask_bits},
+        tile::Tile,
+    },
 };
-use std::collections::HashSet;
+use std::{ops::Deref, sync::Arc};
 
 #[derive(Debug, Clone, Copy)]
-pub struct CrossCheck(u32);
+pub struct CrossCheck(u64);
+
+impl Default for CrossCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CrossCheck {
+    const MASK_BITS: u32 = MAX_LETTERS as u32;
+    const LETTERS: u64 = (1 << Self::MASK_BITS) - 1;
+    const SCORE_MASK: u64 = 0x7FFF;
+    const HAS_WORD: u64 = 1 << 63; // there are tiles across, even if they're all blanks worth 0
+
     pub const fn new() -> Self {
-        Self(0x03FFFFFF) // all letters valid, 0 score
+        Self(Self::LETTERS) // all letters valid, 0 score
+    }
+
+    #[inline]
+    pub fn has_word(self) -> bool {
+        self.0 & Self::HAS_WORD != 0
     }
 
     #[inline]
-    pub fn mask(self) -> u32 {
-        self.0 & 0x03FFFFFF
+    pub fn mask(self) -> u64 {
+        self.0 & Self::LETTERS
     }
 
     #[inline]
-    pub fn score(self) -> u8 {
-        (self.0 >> 26) as u8
+    pub fn score(self) -> u16 {
+        ((self.0 >> Self::MASK_BITS) & Self::SCORE_MASK) as u16
     }
 
+    // a square with tiles across it
     #[inline]
-    pub fn pack(mask: u32, score: u8) -> Self {
-        Self(mask | ((score as u32) << 26))
+    pub fn pack(mask: u64, score: u16) -> Self {
+        Self(mask | ((score as u64 & Self::SCORE_MASK) << Self::MASK_BITS) | Self::HAS_WORD)
     }
 }
 
-// bitmask 0-25 for A-Z and the final bits are used to store the score. swag
-// this gives the score 6 bits, so a max score of 63, which is plenty
+// bitmask with one bit per alphabet letter (up to MAX_LETTERS), then 15 bits of score and a has-word flag. swag
 pub type CrossChecks = [[CrossCheck; BOARD_SIZE]; BOARD_SIZE];
 
 pub fn empty_cross_checks() -> CrossChecks {
     [[CrossCheck::new(); BOARD_SIZE]; BOARD_SIZE]
 }
 
-// finds both anchors and cross checks  from that direction
-pub fn find_anchors(board: &Board, direction: &Direction) -> (Vec<Pos>, CrossChecks) {
-    if board.is_empty() {
-        return (vec![Pos::new(7, 7)], empty_cross_checks());
+fn steps(direction: Direction) -> ((isize, isize), (isize, isize)) {
+    match direction {
+        Direction::Horizontal => ((0, -1), (0, 1)), // left, right
+        Direction::Vertical => ((-1, 0), (1, 0)),   // up, down
     }
+}
 
-    let directions = match direction {
-        Direction::Horizontal => [(0, -1), (0, 1)], // left, right
-        Direction::Vertical => [(-1, 0), (1, 0)],   // up, down
-    };
+// cross check for an empty square from the tiles next to it in `direction`:
+// the letters that make a word with them, and the points of those tiles
+pub fn cross_check_at(board: &Board, pos: Pos, direction: Direction, gaddag: &Gaddag) -> CrossCheck {
+    let dawg = gaddag.dawg();
+    let alphabet = gaddag.alphabet();
+    let (back, forward) = steps(direction);
+    let mut cross_score = 0u16;
+
+    // first tile of the word before the square
+    let mut start = pos;
+    while let Some(prev_pos) = start.offset(back.0, back.1) {
+        if let Some(tile) = board.get_board_tile(prev_pos) {
+            cross_score += alphabet.points(tile) as u16;
+            start = prev_pos;
+        } else {
+            break;
+        }
+    }
 
-    // get all unique anchors
-    let mut anchors = HashSet::new();
-    for (pos, _) in board.tiles() {
-        for &(dir_row, dir_col) in &directions {
-            if let Some(neighbor_pos) = pos.offset(dir_row, dir_col) {
-                if board.get_board_tile(neighbor_pos).is_none() {
-                    anchors.insert(neighbor_pos);
-                }
-            }
+    // last tile of the word after the square
+    let mut end = pos;
+    while let Some(next_pos) = end.offset(forward.0, forward.1) {
+        if let Some(tile) = board.get_board_tile(next_pos) {
+            cross_score += alphabet.points(tile) as u16;
+            end = next_pos;
+        } else {
+            break;
         }
     }
 
-    let mut cross_checks = empty_cross_checks(); // bitsets for valid letters
+    if start == pos && end == pos {
+        return CrossCheck::new();
+    }
 
-    for &pos in &anchors {
-        let mut prefix = Vec::new();
-        let mut suffix = Vec::new();
-        let mut cross_score = 0u8;
+    // walk the prefix once, then try every letter that can follow it against the suffix
+    let mut prefix_node = Some(dawg.root());
+    let mut current_pos = start;
+    while current_pos != pos {
+        let tile = board.get_board_tile(current_pos).unwrap();
+        prefix_node = prefix_node.and_then(|node| dawg.next(node, tile.byte()));
+        current_pos = current_pos.offset(forward.0, forward.1).unwrap();
+    }
 
-        let mut current_pos = pos;
-        while let Some(prev_pos) = current_pos.offset(directions[0].0, directions[0].1) {
-            if let Some(tile) = board.get_board_tile(prev_pos) {
-                prefix.insert(0, tile.byte());
-                cross_score = cross_score.saturating_add(tile.points());
-                current_pos = prev_pos;
-            } else {
-                break;
+    let mut valid_letters = 0u64;
+    if let Some(prefix_node) = prefix_node {
+        dawg.for_each_child(prefix_node, |letter, letter_node| {
+            let mut node = Some(letter_node);
+            let mut current_pos = pos;
+            while current_pos != end {
+                current_pos = current_pos.offset(forward.0, forward.1).unwrap();
+                let tile = board.get_board_tile(current_pos).unwrap();
+                node = node.and_then(|node| dawg.next(node, tile.byte()));
             }
-        }
+            if node.map(|node| dawg.is_terminal(node)).unwrap_or(false) {
+                valid_letters |= 1 << (letter - b'A');
+            }
+            true
+        });
+    }
+    CrossCheck::pack(valid_letters, cross_score)
+}
+
+// a board with its anchors and both directions' cross checks, kept up to date as tiles are placed.
+// placing a tile only touches the squares at the ends of the row and column runs through it.
+// anchors are the empty squares next to a tile, or the centre on an empty board (see Board::anchor_mask).
+// every move covers at least one, and movegen starts each move from the leftmost (topmost) anchor it covers
+#[derive(Debug, Clone)]
+pub struct AnchoredBoard {
+    board: Board,
+    gaddag: Arc<Gaddag>,
+    anchors: [u16; BOARD_SIZE], // a bit per column for each row
+    horizontal: CrossChecks,    // from words running left to right, constrains vertical moves
+    vertical: CrossChecks,      // from words running top to bottom, constrains horizontal moves
+}
+
+impl Deref for AnchoredBoard {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        &self.board
+    }
+}
 
-        current_pos = pos;
-        while let Some(next_pos) = current_pos.offset(directions[1].0, directions[1].1) {
-            if let Some(tile) = board.get_board_tile(next_pos) {
-                suffix.push(tile.byte());
-                cross_score = cross_score.saturating_add(tile.points());
-                current_pos = next_pos;
-            } else {
-                break;
+impl AnchoredBoard {
+    // everything computed from scratch
+    pub fn new(board: Board, gaddag: Arc<Gaddag>) -> Self {
+        let mut anchored = AnchoredBoard {
+            board,
+            gaddag,
+            anchors: [0; BOARD_SIZE],
+            horizontal: empty_cross_checks(),
+            vertical: empty_cross_checks(),
+        };
+        anchored.update_anchors();
+        for row in 0..BOARD_SIZE {
+            for col in mask_bits(anchored.board.neighbor_mask(row)) {
+                anchored.update_cross_checks(Pos::new(row, col));
             }
         }
+        anchored
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn gaddag(&self) -> &Arc<Gaddag> {
+        &self.gaddag
+    }
+
+    pub fn is_anchor(&self, pos: Pos) -> bool {
+        self.anchors[pos.row] & (1 << pos.col) != 0
+    }
 
-        if prefix.is_empty() && suffix.is_empty() {
-            CrossCheck::new();
-            continue;
+    // row by row, left to right
+    pub fn anchors(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..BOARD_SIZE).flat_map(move |row| mask_bits(self.anchors[row]).map(move |col| Pos::new(row, col)))
+    }
+
+    // the cross checks a move in `direction` has to satisfy, from the words running across it
+    pub fn cross_checks(&self, direction: Direction) -> &CrossChecks {
+        match direction {
+            Direction::Horizontal => &self.vertical,
+            Direction::Vertical => &self.horizontal,
         }
+    }
 
-        let mut valid_letters = 0u32;
-        for c in b'A'..=b'Z' {
-            let mut bytes = prefix.clone();
-            bytes.push(c);
-            bytes.extend(suffix.iter());
-            if GADDAG.contains(&bytes) {
-                valid_letters |= 1 << (c - b'A');
+    pub fn place_tile(&mut self, pos: Pos, tile: Tile) -> bool {
+        self.place_tiles([(pos, tile)]) == 1
+    }
+
+    // places all tiles first, then updates the squares around them. returns how many were placed
+    pub fn place_tiles(&mut self, tiles: impl IntoIterator<Item = (Pos, Tile)>) -> usize {
+        let mut placed = Vec::new();
+        for (pos, tile) in tiles {
+            if self.board.place_tile(pos, tile) {
+                placed.push(pos);
+            }
+        }
+        if placed.is_empty() {
+            return 0;
+        }
+        self.update_anchors();
+
+        for &pos in &placed {
+            self.update_cross_checks(pos);
+            for direction in [Direction::Horizontal, Direction::Vertical] {
+                let (back, forward) = steps(direction);
+                // the empty squares at both ends of the run through pos are the only ones whose cross check changed
+                for (d_row, d_col) in [back, forward] {
+                    let mut current_pos = pos;
+                    while let Some(next_pos) = current_pos.offset(d_row, d_col) {
+                        current_pos = next_pos;
+                        if !self.board.is_occupied(next_pos) {
+                            self.update_cross_checks(next_pos);
+                            break;
+                        }
+                    }
+                }
             }
         }
-        cross_checks[pos.row][pos.col] = CrossCheck::pack(valid_letters, cross_score.min(63));
+        placed.len()
     }
 
-    (anchors.into_iter().collect(), cross_checks)
+    // a handful of bit ops per row, cheaper than working out which rows changed
+    fn update_anchors(&mut self) {
+        for row in 0..BOARD_SIZE {
+            self.anchors[row] = self.board.anchor_mask(row);
+        }
+    }
+
+    fn update_cross_checks(&mut self, pos: Pos) {
+        let (row, col) = (pos.row, pos.col);
+        if self.board.is_occupied(pos) {
+            self.horizontal[row][col] = CrossCheck::new();
+            self.vertical[row][col] = CrossCheck::new();
+        } else {
+            self.horizontal[row][col] = cross_check_at(&self.board, pos, Direction::Horizontal, &self.gaddag);
+            self.vertical[row][col] = cross_check_at(&self.board, pos, Direction::Vertical, &self.gaddag);
+        }
+    }
 }
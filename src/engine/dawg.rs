@@ -0,0 +1,62 @@
+use super::gaddag::FstData;
+use fst::Set;
+use fst::raw::CompiledAddr;
+
+// forward word automaton kept next to the gaddag. membership and prefix walks read the board
+// front to back without reversing anything, so they don't allocate
+#[derive(Debug)]
+pub struct Dawg(pub(super) Set<FstData>);
+
+impl Dawg {
+    pub fn root(&self) -> CompiledAddr {
+        self.0.as_fst().root().addr()
+    }
+
+    pub fn node_at(&self, node_addr: CompiledAddr) -> fst::raw::Node<'_> {
+        self.0.as_fst().node(node_addr)
+    }
+
+    pub fn next(&self, node_addr: CompiledAddr, letter: u8) -> Option<CompiledAddr> {
+        let node = self.node_at(node_addr);
+        node.find_input(letter).map(|i| node.transition_addr(i))
+    }
+
+    // follows all letters from node_addr, None as soon as one is missing
+    pub fn walk(&self, node_addr: CompiledAddr, letters: impl IntoIterator<Item = u8>) -> Option<CompiledAddr> {
+        letters.into_iter().try_fold(node_addr, |node_addr, letter| self.next(node_addr, letter))
+    }
+
+    pub fn is_terminal(&self, node_addr: CompiledAddr) -> bool {
+        self.node_at(node_addr).is_final()
+    }
+
+    pub fn contains(&self, word: &[u8]) -> bool {
+        self.walk(self.root(), word.iter().copied())
+            .map(|node_addr| self.is_terminal(node_addr))
+            .unwrap_or(false)
+    }
+
+    pub fn is_prefix(&self, letters: &[u8]) -> bool {
+        self.walk(self.root(), letters.iter().copied()).is_some()
+    }
+
+    // like Gaddag::for_each_child, but also hands over the child node
+    pub fn for_each_child<F>(&self, node_addr: CompiledAddr, mut f: F)
+    where
+        F: FnMut(u8, CompiledAddr) -> bool,
+    {
+        let node = self.node_at(node_addr);
+        for i in 0..node.len() {
+            let transition = node.transition(i);
+            if !f(transition.inp, transition.addr) {
+                break;
+            }
+        }
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        use fst::Streamer;
+        let mut stream = self.0.stream();
+        std::iter::from_fn(move || stream.next().map(|word| word.to_vec()))
+    }
+}
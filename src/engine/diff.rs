@@ -0,0 +1,112 @@
+use super::gaddag::Gaddag;
+use std::{cmp::Ordering, collections::BTreeMap};
+
+// words added and removed between two lexica, grouped by length (in tiles, so CH counts once)
+#[derive(Debug, Clone, Default)]
+pub struct LexiconDiff {
+    pub added: BTreeMap<usize, Vec<String>>,
+    pub removed: BTreeMap<usize, Vec<String>>,
+}
+
+impl LexiconDiff {
+    pub fn added_count(&self) -> usize {
+        self.added.values().map(Vec::len).sum()
+    }
+
+    pub fn removed_count(&self) -> usize {
+        self.removed.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compares two lexica word by word. Both dawgs stream their words in sorted order,
+/// so this is a single merge pass without building any sets.
+/// The lexica have to use the same alphabet, since machine letters are compared directly.
+pub fn diff(old: &Gaddag, new: &Gaddag) -> Result<LexiconDiff, Box<dyn std::error::Error>> {
+    if old.alphabet().letters() != new.alphabet().letters() {
+        return Err(format!(
+            "can't diff lexica with different alphabets ({} and {})",
+            old.alphabet().name,
+            new.alphabet().name
+        )
+        .into());
+    }
+
+    let alphabet = new.alphabet();
+    let mut result = LexiconDiff::default();
+    let mut old_words = old.dawg().words().peekable();
+    let mut new_words = new.dawg().words().peekable();
+    let record = |map: &mut BTreeMap<usize, Vec<String>>, word: Vec<u8>| {
+        map.entry(word.len()).or_default().push(alphabet.decode(&word));
+    };
+
+    loop {
+        let order = match (old_words.peek(), new_words.peek()) {
+            (Some(old_word), Some(new_word)) => old_word.cmp(new_word),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => record(&mut result.removed, old_words.next().unwrap()),
+            Ordering::Greater => record(&mut result.added, new_words.next().unwrap()),
+            Ordering::Equal => {
+                old_words.next();
+                new_words.next();
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::alphabet::Alphabet;
+
+    fn lexicon(words: &[&str], alphabet: Alphabet) -> Gaddag {
+        Gaddag::from_bytes(words.join("\n").as_bytes(), alphabet).unwrap()
+    }
+
+    fn by_length(words: &[(usize, &[&str])]) -> BTreeMap<usize, Vec<String>> {
+        words
+            .iter()
+            .map(|(len, words)| (*len, words.iter().map(|word| word.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn finds_added_and_removed_words() {
+        let old = lexicon(&["AB", "AT", "CAT", "CATS", "DOG"], Alphabet::english());
+        let new = lexicon(&["AT", "CAT", "DOGS", "ZA", "ZAS"], Alphabet::english());
+
+        let changes = diff(&old, &new).unwrap();
+        assert_eq!(changes.added, by_length(&[(2, &["ZA"]), (3, &["ZAS"]), (4, &["DOGS"])]));
+        assert_eq!(changes.removed, by_length(&[(2, &["AB"]), (3, &["DOG"]), (4, &["CATS"])]));
+        assert_eq!((changes.added_count(), changes.removed_count()), (3, 3));
+
+        let back = diff(&new, &old).unwrap();
+        assert_eq!((back.added, back.removed), (changes.removed, changes.added));
+        assert!(diff(&old, &old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn counts_lengths_in_tiles() {
+        let old = lexicon(&["CHE"], Alphabet::spanish());
+        let new = lexicon(&["CHE", "CHES", "LLAMA"], Alphabet::spanish());
+        let changes = diff(&old, &new).unwrap();
+        assert_eq!(changes.added, by_length(&[(3, &["CHES"]), (4, &["LLAMA"])]));
+        assert!(changes.removed.is_empty());
+    }
+
+    #[test]
+    fn needs_the_same_alphabet() {
+        let english = lexicon(&["CAT"], Alphabet::english());
+        let spanish = lexicon(&["CHE"], Alphabet::spanish());
+        assert!(diff(&english, &spanish).is_err());
+    }
+}
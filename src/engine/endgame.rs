@@ -0,0 +1,343 @@
+use super::{actions::Action, moves::PlayedTile};
+use crate::game::Game;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// exact endgame: with the bag empty both racks are known, so it's a two player game of perfect information.
+// negamax with alpha-beta over every placement and pass, deepened one ply at a time until the tree is solved
+// or time runs out. values are the spread the side to move gains from the position on, end of game rack
+// adjustments included, so the same position is worth the same however it was reached
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower, // failed high, the value is at least this
+    Upper, // failed low, the value is at most this
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    depth: usize, // usize::MAX when the subtree was searched to the end of the game
+    value: i32,
+    bound: Bound,
+    best: Option<usize>, // index into the position's actions
+    pv: Vec<Action>,     // only kept for exact entries
+}
+
+#[derive(Debug, Clone)]
+pub struct EndgameResult {
+    pub value: i32,        // spread the side to move gains with best play from here
+    pub final_spread: i32, // the side to move's spread at the end of the principal variation, scores below zero kept
+    pub pv: Vec<Action>,   // best play for both sides, starting with the side to move
+    pub depth: usize,      // deepest finished iteration
+    pub solved: bool,      // the last iteration reached the end of every line, so the value is exact
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+// what a search of a position found. complete if no line was cut off by the depth limit
+struct Searched {
+    value: i32,
+    pv: Vec<Action>,
+    complete: bool,
+}
+
+pub struct EndgameSolver {
+    table: HashMap<u64, Entry>,
+    deadline: Option<Instant>,
+    nodes: u64,
+}
+
+fn spread(game: &Game, player: usize) -> i32 {
+    game.scores[player] as i32 - game.scores[1 - player] as i32
+}
+
+fn rack_points(game: &Game, player: usize) -> i32 {
+    let alphabet = game.gaddag.alphabet();
+    game.racks[player].tiles().iter().map(|&tile| alphabet.points(tile) as i32).sum()
+}
+
+// what `action` gains the side to move in `game`, with the racks counted if it ends the game in `child`.
+// worked out from the racks and not the scores, which Game::end_game stops at zero
+pub(crate) fn gain(game: &Game, action: &Action, child: &Game, ends: bool) -> i32 {
+    let player = game.current_player;
+    let score = match action {
+        Action::Move(mv) => mv.score as i32,
+        _ => 0,
+    };
+    if child.racks[player].is_empty() {
+        score + 2 * rack_points(child, 1 - player)
+    } else if ends || child.is_over() {
+        score - rack_points(child, player) + rack_points(child, 1 - player)
+    } else {
+        score
+    }
+}
+
+fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^ (x >> 31)
+}
+
+// the tiles a player has put down decide both the board and the racks, so the key is built from those
+fn action_key(action: &Action, player: usize) -> u64 {
+    let Action::Move(mv) = action else {
+        return 0;
+    };
+    let mut key = 0;
+    for (pos, played_tile) in mv.tile_positions() {
+        if let PlayedTile::Rack(tile) = played_tile {
+            let square = (pos.row * 16 + pos.col) as u64;
+            key ^= splitmix((player as u64) << 24 | square << 9 | (tile.is_blank() as u64) << 8 | tile.byte() as u64);
+        }
+    }
+    key
+}
+
+const PLAYER_KEY: u64 = 0x5bd1e9955bd1e995;
+const PASSED_KEY: u64 = 0x2545f4914f6cdd1d;
+
+impl Default for EndgameSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EndgameSolver {
+    pub fn new() -> Self {
+        EndgameSolver {
+            table: HashMap::new(),
+            deadline: None,
+            nodes: 0,
+        }
+    }
+
+    /// Solves the position for the player to move. Deepens until the game is solved, `max_depth` plies are searched,
+    /// or `time_limit` runs out, and returns the last finished iteration.
+    /// None when there are tiles in the bag: draws would be random mid-search and the racks unknown.
+    pub fn solve(&mut self, game: &Game, max_depth: usize, time_limit: Option<Duration>) -> Option<EndgameResult> {
+        if !game.bag.is_empty() {
+            return None;
+        }
+        let start = Instant::now();
+        self.table.clear();
+        self.nodes = 0;
+        self.deadline = time_limit.map(|limit| start + limit);
+
+        let player = game.current_player;
+        let mut result = EndgameResult {
+            value: 0,
+            final_spread: spread(game, player),
+            pv: Vec::new(),
+            depth: 0,
+            solved: game.is_over(),
+            nodes: 0,
+            elapsed: Duration::ZERO,
+        };
+
+        // every turn but a pass plays a tile, and two passes in a row end it
+        let longest = 2 * (game.racks[0].tiles().len() + game.racks[1].tiles().len()) + 2;
+        for depth in 1..=max_depth.min(longest) {
+            if result.solved {
+                break;
+            }
+            let Some(searched) = self.search(game, 0, false, depth, -i32::MAX, i32::MAX) else {
+                break;
+            };
+            result.value = searched.value;
+            result.final_spread = spread(game, player) + searched.value;
+            result.pv = searched.pv;
+            result.depth = depth;
+            result.solved = searched.complete;
+        }
+
+        result.nodes = self.nodes;
+        result.elapsed = start.elapsed();
+        Some(result)
+    }
+
+    fn out_of_time(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // None once the deadline passes, the iteration is thrown away then
+    fn search(&mut self, game: &Game, key: u64, passed: bool, depth: usize, mut alpha: i32, beta: i32) -> Option<Searched> {
+        self.nodes += 1;
+        if game.is_over() {
+            return Some(Searched {
+                value: 0,
+                pv: Vec::new(),
+                complete: true,
+            });
+        }
+        if self.nodes.is_multiple_of(256) && self.out_of_time() {
+            return None;
+        }
+
+        let player = game.current_player;
+        if depth == 0 {
+            // a guess: whoever holds more points on their rack is the one more likely to be stuck with them
+            return Some(Searched {
+                value: rack_points(game, 1 - player) - rack_points(game, player),
+                pv: Vec::new(),
+                complete: false,
+            });
+        }
+
+        let node_key = key ^ if player == 1 { PLAYER_KEY } else { 0 } ^ if passed { PASSED_KEY } else { 0 };
+        let original_alpha = alpha;
+        let mut hint = None;
+        if let Some(entry) = self.table.get(&node_key) {
+            hint = entry.best;
+            if entry.depth >= depth {
+                let cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.value >= beta,
+                    Bound::Upper => entry.value <= alpha,
+                };
+                if cutoff {
+                    return Some(Searched {
+                        value: entry.value,
+                        pv: entry.pv.clone(),
+                        complete: entry.depth == usize::MAX,
+                    });
+                }
+            }
+        }
+
+        // the last best move first, then moves that go out, then by score. pass last
+        let rack_len = game.racks[player].tiles().len();
+        let mut actions: Vec<Action> = game.generate_moves().into_iter().map(Action::Move).collect();
+        actions.push(Action::Pass);
+        let mut order: Vec<usize> = (0..actions.len()).collect();
+        order.sort_by_key(|&i| match &actions[i] {
+            _ if Some(i) == hint => (0, 0),
+            Action::Move(mv) => (if mv.tiles_placed() == rack_len { 1 } else { 2 }, -(mv.score as i32)),
+            _ => (3, 0),
+        });
+
+        let mut best: Option<(i32, usize, Vec<Action>)> = None;
+        let mut complete = true;
+        for i in order {
+            let action = &actions[i];
+            let mut child = game.clone();
+            // nothing changes after two passes in a row, so the game ends there with both racks counted against
+            let ends = passed && matches!(action, Action::Pass);
+            let searched = if ends {
+                Searched {
+                    value: 0,
+                    pv: Vec::new(),
+                    complete: true,
+                }
+            } else {
+                child.play_action(action);
+                let child_key = key ^ action_key(action, player);
+                let passing = matches!(action, Action::Pass);
+                self.search(&child, child_key, passing, depth - 1, -beta, -alpha)?
+            };
+            let value = gain(game, action, &child, ends) - searched.value;
+            complete &= searched.complete;
+
+            if best.as_ref().is_none_or(|(best_value, _, _)| value > *best_value) {
+                let mut pv = vec![action.clone()];
+                pv.extend(searched.pv);
+                best = Some((value, i, pv));
+            }
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let (value, index, pv) = best.unwrap();
+        let bound = if value <= original_alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            node_key,
+            Entry {
+                depth: if complete && bound == Bound::Exact { usize::MAX } else { depth },
+                value,
+                bound,
+                best: Some(index),
+                pv: if bound == Bound::Exact { pv.clone() } else { Vec::new() },
+            },
+        );
+        Some(Searched { value, pv, complete })
+    }
+}
+
+/// Solves the endgame for the player to move with a fresh solver. None when there are tiles in the bag.
+pub fn solve_endgame(game: &Game, max_depth: usize, time_limit: Option<Duration>) -> Option<EndgameResult> {
+    EndgameSolver::new().solve(game, max_depth, time_limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Direction, Pos,
+        engine::{
+            anchors::AnchoredBoard,
+            testing::{gaddag, place_word, rack},
+        },
+        game::{alphabet::MAX_TILE_TYPES, board::Board, tile::Tile},
+    };
+
+    // CAT across the centre, both racks known and the bag empty
+    fn position(racks: [&str; 2], scores: [u16; 2]) -> Game {
+        let gaddag = gaddag();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let mut game = Game::init(gaddag.clone());
+        game.board = AnchoredBoard::new(board, gaddag);
+        game.racks = [rack(racks[0]), rack(racks[1])];
+        game.bag.tiles = [0; MAX_TILE_TYPES];
+        game.scores = scores;
+        game
+    }
+
+    #[test]
+    fn goes_out_for_the_most() {
+        // CATS or SCAT for 6, beating AS for 3, then twice the Q
+        let result = solve_endgame(&position(["S", "Q"], [100, 90]), 10, None).unwrap();
+        assert!(result.solved);
+        assert_eq!(result.value, 6 + 2 * 10);
+        assert_eq!(result.final_spread, 10 + 26);
+        assert!(matches!(&result.pv[..], [Action::Move(mv)] if mv.score == 6));
+    }
+
+    #[test]
+    fn passes_when_stuck() {
+        // the Q can't go anywhere, so it's a pass and the S goes out
+        let result = solve_endgame(&position(["Q", "S"], [100, 90]), 10, None).unwrap();
+        assert!(result.solved);
+        assert_eq!(result.value, -26);
+        assert!(matches!(&result.pv[..], [Action::Pass, Action::Move(mv)] if mv.score == 6));
+    }
+
+    #[test]
+    fn counts_racks_past_zero() {
+        // nobody can play: two passes, then Z (10) against X (8). the scores would stop at zero, the value doesn't
+        let result = solve_endgame(&position(["Z", "X"], [3, 3]), 10, None).unwrap();
+        assert!(result.solved);
+        assert_eq!(result.value, -2);
+        assert_eq!(result.final_spread, -2);
+        assert!(matches!(&result.pv[..], [Action::Pass, Action::Pass]));
+    }
+
+    #[test]
+    fn needs_an_empty_bag() {
+        let mut game = position(["S", "Q"], [100, 90]);
+        game.bag.tiles[Tile::letter(b'E').to_index() as usize] = 1;
+        assert!(solve_endgame(&game, 10, None).is_none());
+    }
+}
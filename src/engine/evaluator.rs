@@ -0,0 +1,288 @@
+use super::{
+    actions::{Action, exchanges},
+    leaves::{LeaveTable, RankedMove},
+    moves::{Move, MoveGenerator, PlayedTile},
+};
+use crate::{
+    Direction, Pos,
+    game::{
+        Game,
+        alphabet::Alphabet,
+        bag::MIN_BAG_FOR_EXCHANGE,
+        board::{BOARD_SIZE, Board, Multiplier},
+        rack::{RACK_TILES, Rack},
+        tile::Tile,
+    },
+};
+
+// heuristic weights, all in points. positive is good for the player keeping/playing it
+#[derive(Debug, Clone)]
+pub struct EvalWeights {
+    pub leave_table: f32,     // how much of the LeaveTable value to add, 0 to go on heuristics only
+    pub vowel_balance: f32,   // per vowel away from the ideal count in the leave
+    pub ideal_vowels: f32,    // share of the leave that should be vowels
+    pub duplicate: f32,       // per extra copy of a letter in the leave
+    pub q_without_u: f32,     // keeping Q with no U (or blank) to play it with
+    pub blank: f32,           // per blank kept
+    pub s: f32,               // for keeping an S, duplicates fall under `duplicate`
+    pub opening_hotspot: f32, // per double word square the first move opens to the next player through a vowel
+    pub near_end_scale: f32,  // leave terms are scaled by this once the bag has fewer than a rack of tiles left
+    pub stuck_points: f32,    // per point of the leave once the bag runs out, those tiles may not get played
+    pub vowels: Vec<String>,  // labels counted as vowels
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            leave_table: 1.0,
+            vowel_balance: -2.0,
+            ideal_vowels: 0.4,
+            duplicate: -3.0,
+            q_without_u: -7.0,
+            blank: 25.0,
+            s: 8.0,
+            opening_hotspot: -1.5,
+            near_end_scale: 0.5,
+            stuck_points: -2.0,
+            vowels: ["A", "E", "I", "O", "U"].iter().map(|label| label.to_string()).collect(),
+        }
+    }
+}
+
+// static evaluation: score plus what the leave and the board position are worth by the weights
+#[derive(Debug, Clone, Default)]
+pub struct Evaluator {
+    pub weights: EvalWeights,
+    pub leaves: LeaveTable,
+}
+
+impl Evaluator {
+    pub fn new(weights: EvalWeights, leaves: LeaveTable) -> Self {
+        Evaluator { weights, leaves }
+    }
+
+    fn is_vowel(&self, tile: Tile, alphabet: &Alphabet) -> bool {
+        !tile.is_blank()
+            && self
+                .weights
+                .vowels
+                .iter()
+                .any(|vowel| vowel.eq_ignore_ascii_case(alphabet.byte_label(tile.byte())))
+    }
+
+    /// What keeping `leave` is worth, with `bag_len` tiles in the bag after the move.
+    pub fn leave_value(&self, leave: &[Tile], bag_len: usize, alphabet: &Alphabet) -> f32 {
+        let weights = &self.weights;
+        if bag_len == 0 {
+            let points: u32 = leave.iter().map(|&tile| alphabet.points(tile) as u32).sum();
+            return weights.stuck_points * points as f32;
+        }
+        if leave.is_empty() {
+            return 0.0;
+        }
+
+        let mut value = weights.leave_table * self.leaves.value(leave);
+
+        let vowels = leave.iter().filter(|&&tile| self.is_vowel(tile, alphabet)).count() as f32;
+        value += weights.vowel_balance * (vowels - leave.len() as f32 * weights.ideal_vowels).abs();
+
+        let mut counts = [0u8; 256];
+        for tile in leave.iter().filter(|tile| !tile.is_blank()) {
+            counts[tile.byte() as usize] += 1;
+        }
+        let duplicates: u32 = counts.iter().map(|&count| count.saturating_sub(1) as u32).sum();
+        value += weights.duplicate * duplicates as f32;
+
+        let blanks = leave.iter().filter(|tile| tile.is_blank()).count();
+        value += weights.blank * blanks as f32;
+
+        let letter = |label: &str| alphabet.encode(label).filter(|bytes| bytes.len() == 1).map(|bytes| bytes[0]);
+        if let Some(s) = letter("S")
+            && counts[s as usize] > 0
+        {
+            value += weights.s;
+        }
+        if let (Some(q), Some(u)) = (letter("Q"), letter("U"))
+            && counts[q as usize] > 0
+            && counts[u as usize] == 0
+            && blanks == 0
+        {
+            value += weights.q_without_u;
+        }
+
+        if bag_len < RACK_TILES {
+            value *= weights.near_end_scale;
+        }
+        value
+    }
+
+    // double word squares the opening move opens: on the line across one of its vowels and close enough for a rack to
+    // reach from it. a vowel between two of them (the A of ACTS from 7,4 across) opens a double-double lane and counts twice.
+    // squares no vowel of the play lines up with stay as closed as they were, so they cost nothing
+    fn opening_penalty(&self, mv: &Move, board: &Board, alphabet: &Alphabet) -> f32 {
+        let mut hotspots = 0;
+        for (pos, played_tile) in mv.tile_positions() {
+            let PlayedTile::Rack(tile) = played_tile else {
+                continue;
+            };
+            if !self.is_vowel(tile, alphabet) {
+                continue;
+            }
+            let across = (0..BOARD_SIZE).map(|i| match mv.direction {
+                Direction::Horizontal => Pos::new(i, pos.col),
+                Direction::Vertical => Pos::new(pos.row, i),
+            });
+            hotspots += across
+                .filter(|&square| square != pos && square.row.abs_diff(pos.row) + square.col.abs_diff(pos.col) <= RACK_TILES)
+                .filter(|&square| matches!(board.get_multiplier(square), Some(Multiplier::DoubleWord)))
+                .count();
+        }
+        self.weights.opening_hotspot * hotspots as f32
+    }
+
+    /// Score plus the heuristics for `mv` played from `rack` on `board`, with `bag_len` tiles in the bag before the move.
+    pub fn evaluate(&self, mv: &Move, board: &Board, rack: &Rack, bag_len: usize, alphabet: &Alphabet) -> f32 {
+        let leave = mv.leave(rack);
+        let placed = rack.tiles().len() - leave.tiles().len();
+        let mut value = mv.score as f32 + self.leave_value(leave.tiles(), bag_len.saturating_sub(placed), alphabet);
+        if board.is_empty() {
+            value += self.opening_penalty(mv, board, alphabet);
+        }
+        value
+    }
+
+    /// Every move of the generator by evaluation, best first. `leave` in each is the evaluation minus the score.
+    pub fn rank(&self, generator: &MoveGenerator, bag_len: usize) -> Vec<RankedMove> {
+        let alphabet = generator.board.gaddag().alphabet();
+        let mut ranked: Vec<RankedMove> = generator
+            .generate_moves()
+            .into_iter()
+            .map(|mv| {
+                let equity = self.evaluate(&mv, &generator.board, &generator.rack, bag_len, alphabet);
+                RankedMove {
+                    leave: equity - mv.score as f32,
+                    equity,
+                    mv,
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.equity.total_cmp(&a.equity));
+        ranked
+    }
+
+    /// The baseline player: the best evaluated move, or an exchange when the tiles kept are worth more, otherwise pass.
+    pub fn choose_action(&self, game: &Game) -> Action {
+        let alphabet = game.gaddag.alphabet();
+        let rack = &game.racks[game.current_player];
+        let generator = MoveGenerator {
+            board: game.board.clone(),
+            rack: rack.clone(),
+        };
+
+        let mut best = self
+            .rank(&generator, game.bag.len())
+            .into_iter()
+            .next()
+            .map(|ranked| (ranked.equity, Action::Move(ranked.mv)));
+        if game.bag.len() >= MIN_BAG_FOR_EXCHANGE {
+            for exchange in exchanges(rack) {
+                let mut kept = rack.clone();
+                for &tile in &exchange {
+                    kept.remove_tile(tile);
+                }
+                let equity = self.leave_value(kept.tiles(), game.bag.len(), alphabet);
+                if best.as_ref().is_none_or(|(best_equity, _)| equity > *best_equity) {
+                    best = Some((equity, Action::Swap(exchange)));
+                }
+            }
+        }
+        best.map_or(Action::Pass, |(_, action)| action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        anchors::AnchoredBoard,
+        testing::{gaddag, place_word, rack},
+    };
+
+    fn ranked(board: Board, letters: &str) -> Vec<RankedMove> {
+        let generator = MoveGenerator {
+            board: AnchoredBoard::new(board, gaddag()),
+            rack: rack(letters),
+        };
+        Evaluator::default().rank(&generator, 50)
+    }
+
+    fn find<'a>(ranked: &'a [RankedMove], word: &str, pos: Pos, direction: Direction) -> &'a RankedMove {
+        let alphabet = Alphabet::english();
+        ranked
+            .iter()
+            .find(|ranked| {
+                ranked.mv.get_word_string(&alphabet) == word
+                    && ranked.mv.pos == pos
+                    && matches!(
+                        (&ranked.mv.direction, &direction),
+                        (Direction::Horizontal, Direction::Horizontal) | (Direction::Vertical, Direction::Vertical)
+                    )
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn ranks_equal_scores_by_leave() {
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let ranked = ranked(board, "ERS");
+        assert!(ranked.windows(2).all(|pair| pair[0].equity >= pair[1].equity));
+
+        // AR and AE down from the A both score 3. keeping ES: 1 vowel of an ideal 0.8 is -0.4, the S +8.
+        // keeping RS: 0.8 vowels short is -1.6, the S +8
+        let ar = find(&ranked, "AR", Pos::new(7, 8), Direction::Vertical);
+        let ae = find(&ranked, "AE", Pos::new(7, 8), Direction::Vertical);
+        assert_eq!((ar.mv.score, ae.mv.score), (3, 3));
+        assert!((ar.leave - 7.6).abs() < 1e-4);
+        assert!((ae.leave - 6.4).abs() < 1e-4);
+        let position = |word: &RankedMove| ranked.iter().position(|ranked| ranked.mv == word.mv).unwrap();
+        assert!(position(ar) < position(ae));
+    }
+
+    #[test]
+    fn penalises_only_openings_that_open_a_double_word() {
+        let ranked = ranked(Board::new(), "ACST");
+        let evaluator = Evaluator::default();
+        let alphabet = Alphabet::english();
+        let penalty = |ranked: &RankedMove| {
+            let leave = ranked.mv.leave(&rack("ACST"));
+            ranked.leave - evaluator.leave_value(leave.tiles(), 50 - ranked.mv.tiles_placed(), &alphabet)
+        };
+
+        // the A of ACTS lands on column 4, between the double words at 4,4 and 10,4
+        let acts = find(&ranked, "ACTS", Pos::new(7, 4), Direction::Horizontal);
+        // the same letters with a consonant there and the A on column 5, which has none
+        let cats = find(&ranked, "CATS", Pos::new(7, 4), Direction::Horizontal);
+        assert_eq!(acts.mv.score, cats.mv.score);
+        assert!((penalty(acts) - 2.0 * evaluator.weights.opening_hotspot).abs() < 1e-4);
+        assert_eq!(penalty(cats), 0.0);
+        assert_eq!(penalty(find(&ranked, "CATS", Pos::new(7, 7), Direction::Horizontal)), 0.0);
+        assert_eq!(penalty(find(&ranked, "ACTS", Pos::new(4, 7), Direction::Vertical)), penalty(acts));
+        assert!(ranked.iter().position(|ranked| ranked.mv == cats.mv) < ranked.iter().position(|ranked| ranked.mv == acts.mv));
+    }
+
+    #[test]
+    fn swaps_or_passes_without_a_play() {
+        let gaddag = gaddag();
+        let mut game = Game::init(gaddag.clone());
+        game.racks[0] = rack("QVVWWXZ");
+        assert!(matches!(Evaluator::default().choose_action(&game), Action::Swap(_)));
+
+        // too few tiles left to swap
+        game.bag.draw_tiles(game.bag.len() - (MIN_BAG_FOR_EXCHANGE - 1));
+        assert!(matches!(Evaluator::default().choose_action(&game), Action::Pass));
+
+        game.racks[0] = rack("ACST");
+        assert!(matches!(Evaluator::default().choose_action(&game), Action::Move(_)));
+    }
+}
@@ -1,42 +1,195 @@
+use super::dawg::Dawg;
+use crate::game::alphabet::Alphabet;
 use fst::Set;
-use fst::raw::CompiledAddr;
-use lazy_static::lazy_static;
-use std::collections::BTreeSet;
-
-lazy_static! {
-    pub static ref GADDAG: Gaddag = {
-        if let Ok(gaddag) = Gaddag::load("wordlists/CSW24.fst") {
-            gaddag
-        } else {
-            let gaddag = Gaddag::from_wordlist("wordlists/CSW24.txt");
-            gaddag.save("wordlists/CSW24.fst").unwrap();
-            gaddag
+use fst::raw::{CompiledAddr, Fst};
+use memmap2::Mmap;
+use std::{collections::BTreeSet, fmt, fs::File, io::Write, path::Path, sync::Arc};
+
+// on-disk layout: header, the alphabet (see Alphabet::to_text), the gaddag fst, then the dawg fst
+// magic (8) | format version (u32) | word count (u32) | source hash (u64) | alphabet length (u32) | gaddag length (u64),
+// all little endian
+const MAGIC: &[u8; 8] = b"SCRABGAD";
+const FORMAT_VERSION: u32 = 3;
+const HEADER_LEN: usize = 36;
+
+#[derive(Debug)]
+pub enum GaddagError {
+    Io(std::io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    Truncated,
+    Corrupt(fst::Error),
+    BadAlphabet(String),
+    UnknownLetters(String), // a wordlist line that isn't made of alphabet letters
+}
+
+impl fmt::Display for GaddagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GaddagError::Io(err) => write!(f, "gaddag io error: {}", err),
+            GaddagError::BadMagic => write!(f, "not a gaddag file (bad magic)"),
+            GaddagError::UnsupportedVersion(version) => {
+                write!(f, "unsupported gaddag format version {} (expected {})", version, FORMAT_VERSION)
+            }
+            GaddagError::Truncated => write!(f, "gaddag file is truncated"),
+            GaddagError::Corrupt(err) => write!(f, "gaddag file is corrupt: {}", err),
+            GaddagError::BadAlphabet(err) => write!(f, "bad alphabet: {}", err),
+            GaddagError::UnknownLetters(word) => write!(f, "{:?} is not made of letters of the alphabet", word),
+        }
+    }
+}
+
+impl std::error::Error for GaddagError {}
+
+impl From<std::io::Error> for GaddagError {
+    fn from(err: std::io::Error) -> Self {
+        GaddagError::Io(err)
+    }
+}
+
+impl From<fst::Error> for GaddagError {
+    fn from(err: fst::Error) -> Self {
+        GaddagError::Corrupt(err)
+    }
+}
+
+// fst bytes either built in memory or a section of a mapped file
+#[derive(Debug)]
+pub enum FstData {
+    Owned(Vec<u8>),
+    Mapped(Arc<Mmap>, std::ops::Range<usize>),
+}
+
+impl AsRef<[u8]> for FstData {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            FstData::Owned(bytes) => bytes,
+            FstData::Mapped(mmap, range) => &mmap[range.clone()],
         }
-    };
+    }
+}
+
+// fnv-1a, stable across builds unlike DefaultHasher
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// identifies what a gaddag was built from: the wordlist and the alphabet it was read with
+pub fn source_hash(source: &[u8], alphabet: &Alphabet) -> u64 {
+    fnv1a(fnv1a(0xcbf29ce484222325, source), alphabet.to_text().as_bytes())
 }
 
 // from https://github.com/amedeedaboville/fst-gaddag
 #[derive(Debug)]
-pub struct Gaddag(pub Set<Vec<u8>>);
+pub struct Gaddag {
+    set: Set<FstData>,
+    dawg: Dawg,
+    alphabet: Alphabet,
+    word_count: u32,
+    source_hash: u64,
+}
 pub const DELIMITER: u8 = b'+';
 
 impl Gaddag {
-    pub fn save(&self, path: &str) -> std::io::Result<()> {
-        std::fs::write(path, self.0.as_fst().as_bytes())
+    pub fn save(&self, path: &str) -> Result<(), GaddagError> {
+        let mut file = File::create(path)?;
+        file.write_all(MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        file.write_all(&self.word_count.to_le_bytes())?;
+        file.write_all(&self.source_hash.to_le_bytes())?;
+        let alphabet_text = self.alphabet.to_text();
+        let gaddag_bytes = self.set.as_fst().as_bytes();
+        file.write_all(&(alphabet_text.len() as u32).to_le_bytes())?;
+        file.write_all(&(gaddag_bytes.len() as u64).to_le_bytes())?;
+        file.write_all(alphabet_text.as_bytes())?;
+        file.write_all(gaddag_bytes)?;
+        file.write_all(self.dawg.0.as_fst().as_bytes())?;
+        Ok(())
     }
 
-    pub fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Gaddag(Set::new(std::fs::read(path)?)?))
+    pub fn load(path: &str) -> Result<Self, GaddagError> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() < HEADER_LEN {
+            return Err(GaddagError::Truncated);
+        }
+        if &mmap[..8] != MAGIC {
+            return Err(GaddagError::BadMagic);
+        }
+        let version = u32::from_le_bytes(mmap[8..12].try_into().unwrap());
+        if version != FORMAT_VERSION {
+            return Err(GaddagError::UnsupportedVersion(version));
+        }
+        let word_count = u32::from_le_bytes(mmap[12..16].try_into().unwrap());
+        let source_hash = u64::from_le_bytes(mmap[16..24].try_into().unwrap());
+        let alphabet_end = HEADER_LEN.saturating_add(u32::from_le_bytes(mmap[24..28].try_into().unwrap()) as usize);
+        let gaddag_end = alphabet_end.saturating_add(u64::from_le_bytes(mmap[28..36].try_into().unwrap()) as usize);
+        if gaddag_end > mmap.len() {
+            return Err(GaddagError::Truncated);
+        }
+
+        let alphabet_text = std::str::from_utf8(&mmap[HEADER_LEN..alphabet_end]).map_err(|err| GaddagError::BadAlphabet(err.to_string()))?;
+        let alphabet = Alphabet::from_text(alphabet_text).map_err(GaddagError::BadAlphabet)?;
+
+        let mmap = Arc::new(mmap);
+        let fst = Fst::new(FstData::Mapped(mmap.clone(), alphabet_end..gaddag_end))?;
+        let dawg = Fst::new(FstData::Mapped(mmap.clone(), gaddag_end..mmap.len()))?;
+        // checksums, so a damaged file fails here and not halfway through movegen
+        fst.verify()?;
+        dawg.verify()?;
+        Ok(Gaddag {
+            set: Set::from(fst),
+            dawg: Dawg(Set::from(dawg)),
+            alphabet,
+            word_count,
+            source_hash,
+        })
+    }
+
+    /// Loads `fst_path` if it was built from the current contents of `wordlist_path` with this alphabet,
+    /// otherwise (re)builds it from the wordlist and saves it.
+    /// Without a wordlist the fst is loaded as is, with the alphabet stored in it.
+    pub fn load_or_build(fst_path: &str, wordlist_path: &str, alphabet: &Alphabet) -> Result<Self, GaddagError> {
+        if !Path::new(wordlist_path).exists() {
+            return Gaddag::load(fst_path);
+        }
+
+        let source = std::fs::read(wordlist_path)?;
+        if let Ok(gaddag) = Gaddag::load(fst_path)
+            && gaddag.source_hash == source_hash(&source, alphabet)
+        {
+            return Ok(gaddag);
+        }
+
+        let gaddag = Gaddag::from_bytes(&source, alphabet.clone())?;
+        gaddag.save(fst_path)?;
+        Ok(gaddag)
     }
 
-    pub fn from_wordlist(path: &str) -> Self {
-        use std::io::BufRead;
-        let file = std::fs::File::open(path).unwrap();
-        let reader = std::io::BufReader::new(file);
-        let words: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
+    pub fn from_wordlist(path: &str, alphabet: Alphabet) -> Result<Self, GaddagError> {
+        Gaddag::from_bytes(&std::fs::read(path)?, alphabet)
+    }
+
+    // one word per line, in utf-8
+    pub fn from_bytes(source: &[u8], alphabet: Alphabet) -> Result<Self, GaddagError> {
+        let text = std::str::from_utf8(source).map_err(|_| GaddagError::UnknownLetters("wordlist is not valid utf-8".to_string()))?;
         let mut entries: BTreeSet<Vec<u8>> = BTreeSet::new();
-        for word in words {
-            let bytes = word.as_bytes();
+        let mut words = BTreeSet::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let word = alphabet.encode(line).ok_or_else(|| GaddagError::UnknownLetters(line.to_string()))?;
+            if !words.insert(word.clone()) {
+                continue;
+            }
+            let bytes = word.as_slice();
 
             // full reversed word
             entries.insert(bytes.to_vec().iter().rev().cloned().collect()); // nialpxe
@@ -55,28 +208,51 @@ impl Gaddag {
             }
         }
 
-        Gaddag(Set::from_iter(entries).unwrap())
+        let bytes = Set::from_iter(entries)?.into_fst().into_inner();
+        let dawg_bytes = Set::from_iter(&words)?.into_fst().into_inner();
+        Ok(Gaddag {
+            set: Set::from(Fst::new(FstData::Owned(bytes))?),
+            dawg: Dawg(Set::from(Fst::new(FstData::Owned(dawg_bytes))?)),
+            word_count: words.len() as u32,
+            source_hash: source_hash(source, &alphabet),
+            alphabet,
+        })
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn word_count(&self) -> u32 {
+        self.word_count
+    }
+
+    pub fn source_hash(&self) -> u64 {
+        self.source_hash
+    }
+
+    pub fn dawg(&self) -> &Dawg {
+        &self.dawg
     }
 
     ///Returns true if the given word is in the dictionary.
-    ///Searches for `^input.rev()$`.
+    ///Looked up in the forward dawg, so nothing gets reversed or allocated.
     pub fn contains(&self, word: &[u8]) -> bool {
-        let search_vec: Vec<u8> = word.iter().rev().cloned().collect();
-        self.0.contains(search_vec)
+        self.dawg.contains(word)
+    }
+
+    pub fn root(&self) -> CompiledAddr {
+        self.set.as_fst().root().addr()
     }
 
-    pub fn node_at(&self, node_addr: CompiledAddr) -> fst::raw::Node {
-        self.0.as_fst().node(node_addr)
+    pub fn node_at(&self, node_addr: CompiledAddr) -> fst::raw::Node<'_> {
+        self.set.as_fst().node(node_addr)
     }
 
     ///Attempts to follow the node in the GADDAG, and returns the next node.
     pub fn can_next(&self, node_addr: CompiledAddr, next: u8) -> Option<CompiledAddr> {
         let current_node = self.node_at(node_addr);
-        if let Some(i) = current_node.find_input(next) {
-            Some(current_node.transition(i).addr)
-        } else {
-            None
-        }
+        current_node.find_input(next).map(|i| current_node.transition(i).addr)
     }
 
     pub fn is_terminal(&self, node_addr: CompiledAddr) -> bool {
@@ -97,3 +273,125 @@ impl Gaddag {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: &str = "CAT\nCATS\nAT\nACT\nSCAT\nTA\n";
+
+    // a file in the temp dir for one test, removed when it's dropped
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("scrab-{}-{}", std::process::id(), name));
+            TempFile(path.to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    // the word's entries followed through the gaddag: reversed whole, and every prefix reversed + suffix
+    fn in_gaddag(gaddag: &Gaddag, word: &[u8]) -> bool {
+        let follow = |path: Vec<u8>| {
+            path.into_iter()
+                .try_fold(gaddag.root(), |node, letter| gaddag.can_next(node, letter))
+                .is_some_and(|node| gaddag.is_terminal(node))
+        };
+        let reversed = follow(word.iter().rev().copied().collect());
+        for i in 1..word.len() {
+            let mut path: Vec<u8> = word[..i].iter().rev().copied().collect();
+            path.push(DELIMITER);
+            path.extend(&word[i..]);
+            assert_eq!(follow(path), reversed);
+        }
+        reversed
+    }
+
+    #[test]
+    fn dawg_agrees_with_the_gaddag() {
+        let gaddag = Gaddag::from_bytes(WORDS.as_bytes(), Alphabet::english()).unwrap();
+        let alphabet = gaddag.alphabet();
+        for word in [
+            "CAT", "CATS", "AT", "ACT", "SCAT", "TA", "C", "CA", "ATS", "CATSS", "SCA", "TAC", "ACTS", "A", "Z",
+        ] {
+            let bytes = alphabet.encode(word).unwrap();
+            assert_eq!(gaddag.contains(&bytes), WORDS.lines().any(|line| line == word), "{}", word);
+            assert_eq!(gaddag.contains(&bytes), in_gaddag(&gaddag, &bytes), "{}", word);
+        }
+        assert_eq!(gaddag.word_count(), 6);
+    }
+
+    #[test]
+    fn loads_what_it_saved() {
+        let file = TempFile::new("saved.gaddag");
+        let gaddag = Gaddag::from_bytes(WORDS.as_bytes(), Alphabet::spanish()).unwrap();
+        gaddag.save(&file.0).unwrap();
+
+        let loaded = Gaddag::load(&file.0).unwrap();
+        assert_eq!(loaded.alphabet(), gaddag.alphabet());
+        assert_eq!(loaded.word_count(), gaddag.word_count());
+        assert_eq!(loaded.source_hash(), gaddag.source_hash());
+        assert_eq!(loaded.dawg().words().collect::<Vec<_>>(), gaddag.dawg().words().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rejects_damaged_files() {
+        let file = TempFile::new("damaged.gaddag");
+        Gaddag::from_bytes(WORDS.as_bytes(), Alphabet::english()).unwrap().save(&file.0).unwrap();
+        let bytes = std::fs::read(&file.0).unwrap();
+        let load = |bytes: &[u8]| {
+            std::fs::write(&file.0, bytes).unwrap();
+            Gaddag::load(&file.0)
+        };
+
+        assert!(matches!(load(&bytes[..20]), Err(GaddagError::Truncated)));
+        assert!(matches!(load(&bytes[..HEADER_LEN + 10]), Err(GaddagError::Truncated)));
+        assert!(matches!(load(&bytes[..bytes.len() - 1]), Err(GaddagError::Corrupt(_))));
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[..8].copy_from_slice(b"SCRABPOS");
+        assert!(matches!(load(&wrong_magic), Err(GaddagError::BadMagic)));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[8..12].copy_from_slice(&(FORMAT_VERSION - 1).to_le_bytes());
+        assert!(matches!(load(&wrong_version), Err(GaddagError::UnsupportedVersion(version)) if version == FORMAT_VERSION - 1));
+
+        assert!(load(&bytes).is_ok());
+    }
+
+    #[test]
+    fn rebuilds_from_a_changed_wordlist() {
+        let (fst, wordlist) = (TempFile::new("stale.gaddag"), TempFile::new("stale.txt"));
+        let alphabet = Alphabet::english();
+        let cat = alphabet.encode("CAT").unwrap();
+        let dog = alphabet.encode("DOG").unwrap();
+
+        std::fs::write(&wordlist.0, "CAT\nAT\n").unwrap();
+        let built = Gaddag::load_or_build(&fst.0, &wordlist.0, &alphabet).unwrap();
+        assert!(built.contains(&cat) && !built.contains(&dog));
+        assert_eq!(Gaddag::load(&fst.0).unwrap().source_hash(), built.source_hash());
+
+        // the saved file no longer matches the wordlist's hash
+        std::fs::write(&wordlist.0, "CAT\nAT\nDOG\n").unwrap();
+        let rebuilt = Gaddag::load_or_build(&fst.0, &wordlist.0, &alphabet).unwrap();
+        assert!(rebuilt.contains(&dog));
+        assert_ne!(rebuilt.source_hash(), built.source_hash());
+        assert!(Gaddag::load(&fst.0).unwrap().contains(&dog));
+
+        // same words, other alphabet
+        let spanish = Alphabet::spanish();
+        let rebuilt = Gaddag::load_or_build(&fst.0, &wordlist.0, &spanish).unwrap();
+        assert_eq!(rebuilt.alphabet(), &spanish);
+
+        // without the wordlist whatever was saved is loaded
+        std::fs::remove_file(&wordlist.0).unwrap();
+        let loaded = Gaddag::load_or_build(&fst.0, &wordlist.0, &alphabet).unwrap();
+        assert_eq!(loaded.alphabet(), &spanish);
+    }
+}
@@ -0,0 +1,165 @@
+use super::gaddag::{DELIMITER, Gaddag};
+use fst::raw::CompiledAddr;
+use std::collections::BTreeSet;
+
+// hooks and extensions come straight out of the gaddag layout:
+// after walking `word.rev()` from the root, every further letter is one more letter in front of the word,
+// and the DELIMITER edge switches to letters after it
+
+#[derive(Debug, Clone, Default)]
+pub struct WordExtensions {
+    pub front_hooks: Vec<String>,
+    pub back_hooks: Vec<String>,
+    pub front: Vec<String>, // letters only before the word: REWORD
+    pub back: Vec<String>,  // letters only after the word: WORDS
+    pub inner: Vec<String>, // letters on both sides: SWORDS
+}
+
+impl WordExtensions {
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty() && self.inner.is_empty()
+    }
+}
+
+impl Gaddag {
+    // node reached after reading the word back to front, if any word contains it
+    fn word_node(&self, word: &str) -> Option<CompiledAddr> {
+        let mut node_addr = self.root();
+        for letter in self.alphabet().encode(word)?.into_iter().rev() {
+            node_addr = self.can_next(node_addr, letter)?;
+        }
+        Some(node_addr)
+    }
+
+    pub fn front_hooks(&self, word: &str) -> Vec<String> {
+        let mut hooks = Vec::new();
+        if let Some(node_addr) = self.word_node(word) {
+            for transition in self.node_at(node_addr).transitions() {
+                if transition.inp != DELIMITER && self.is_terminal(transition.addr) {
+                    hooks.push(self.alphabet().byte_label(transition.inp).to_string());
+                }
+            }
+        }
+        hooks
+    }
+
+    pub fn back_hooks(&self, word: &str) -> Vec<String> {
+        let mut hooks = Vec::new();
+        if let Some(delimiter_node) = self.word_node(word).and_then(|node_addr| self.can_next(node_addr, DELIMITER)) {
+            for transition in self.node_at(delimiter_node).transitions() {
+                if self.is_terminal(transition.addr) {
+                    hooks.push(self.alphabet().byte_label(transition.inp).to_string());
+                }
+            }
+        }
+        hooks
+    }
+
+    /// Hooks plus every longer word that contains `word` as a contiguous substring.
+    /// Words come back in the alphabet's labels, whatever case `word` was written in.
+    pub fn extensions(&self, word: &str) -> WordExtensions {
+        let Some(letters) = self.alphabet().encode(word) else {
+            return WordExtensions::default();
+        };
+        let word = self.alphabet().decode(&letters);
+        let mut extensions = WordExtensions {
+            front_hooks: self.front_hooks(&word),
+            back_hooks: self.back_hooks(&word),
+            ..Default::default()
+        };
+
+        if let Some(node_addr) = self.word_node(&word) {
+            let mut front = BTreeSet::new();
+            let mut back = BTreeSet::new();
+            let mut inner = BTreeSet::new();
+            self.extend_front(node_addr, &word, &mut Vec::new(), &mut front, &mut back, &mut inner);
+            extensions.front = front.into_iter().collect();
+            extensions.back = back.into_iter().collect();
+            extensions.inner = inner.into_iter().collect();
+        }
+
+        extensions
+    }
+
+    // `before` is kept in reverse, the same order the gaddag reads it
+    fn extend_front(
+        &self,
+        node_addr: CompiledAddr,
+        word: &str,
+        before: &mut Vec<u8>,
+        front: &mut BTreeSet<String>,
+        back: &mut BTreeSet<String>,
+        inner: &mut BTreeSet<String>,
+    ) {
+        let prefix = self.alphabet().decode(&before.iter().rev().copied().collect::<Vec<u8>>());
+        if !before.is_empty() && self.is_terminal(node_addr) {
+            front.insert(format!("{}{}", prefix, word));
+        }
+
+        for transition in self.node_at(node_addr).transitions() {
+            if transition.inp == DELIMITER {
+                let target = if before.is_empty() { &mut *back } else { &mut *inner };
+                self.extend_back(transition.addr, &format!("{}{}", prefix, word), &mut String::new(), target);
+            } else {
+                before.push(transition.inp);
+                self.extend_front(transition.addr, word, before, front, back, inner);
+                before.pop();
+            }
+        }
+    }
+
+    fn extend_back(&self, node_addr: CompiledAddr, start: &str, after: &mut String, words: &mut BTreeSet<String>) {
+        if !after.is_empty() && self.is_terminal(node_addr) {
+            words.insert(format!("{}{}", start, after));
+        }
+
+        for transition in self.node_at(node_addr).transitions() {
+            let len = after.len();
+            after.push_str(self.alphabet().byte_label(transition.inp));
+            self.extend_back(transition.addr, start, after, words);
+            after.truncate(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::testing::gaddag, game::alphabet::Alphabet};
+
+    #[test]
+    fn hooks_and_extensions() {
+        let gaddag = gaddag();
+        assert_eq!(gaddag.front_hooks("AT"), ["C", "E", "O", "R"]);
+        assert_eq!(gaddag.back_hooks("CAT"), ["S"]);
+        assert!(gaddag.back_hooks("CATS").is_empty());
+        assert!(gaddag.front_hooks("QI").is_empty());
+
+        let extensions = gaddag.extensions("cat");
+        assert_eq!(extensions.front_hooks, ["S"]);
+        assert_eq!(extensions.back_hooks, ["S"]);
+        assert_eq!(extensions.front, ["SCAT"]);
+        assert_eq!(extensions.back, ["CATS"]);
+        assert!(extensions.inner.is_empty());
+        assert!(gaddag.extensions("QI").is_empty());
+    }
+
+    #[test]
+    fn multi_letter_tiles() {
+        let words = "CHE\nCHES\nACHE\nNOCHE\nLLAMA\nLLAMAS\n";
+        let gaddag = Gaddag::from_bytes(words.as_bytes(), Alphabet::spanish()).unwrap();
+        assert_eq!(gaddag.front_hooks("AMA"), ["LL"]);
+        assert_eq!(gaddag.front_hooks("che"), ["A"]);
+
+        let extensions = gaddag.extensions("ama");
+        assert_eq!(extensions.front_hooks, ["LL"]);
+        assert_eq!(extensions.front, ["LLAMA"]);
+        assert_eq!(extensions.inner, ["LLAMAS"]);
+
+        // CH is one tile, so NOCHE is NO + CH + E
+        let extensions = gaddag.extensions("ch");
+        assert_eq!(extensions.back, ["CHE", "CHES"]);
+        assert_eq!(extensions.inner, ["ACHE", "NOCHE"]);
+        assert!(gaddag.extensions("ñu").is_empty());
+    }
+}
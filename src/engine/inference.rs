@@ -0,0 +1,351 @@
+use super::{
+    actions::Action,
+    evaluator::Evaluator,
+    leaves::{LeaveKey, leave_key},
+    moves::{MoveGenerator, PlayedTile},
+};
+use crate::game::{Game, alphabet::MAX_TILE_TYPES, rack::Rack, tile::Tile};
+use rand::Rng;
+use std::collections::HashMap;
+
+// what the opponent kept, from what they did with their turn: leaves are sampled from the unseen tiles,
+// and a leave counts for more the closer the opponent's turn comes to the best one the equity model finds with it.
+// a pass that left a 40 point hook on the board makes every leave that could've played it unlikely.
+// an exchange only shows how many tiles went back, so there leaves just count for what the evaluator thinks they're worth
+
+#[derive(Debug, Clone)]
+pub struct InferenceConfig {
+    pub samples: usize,
+    pub temperature: f32, // equity given up per factor of e less likely
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        InferenceConfig {
+            samples: 500,
+            temperature: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InferredLeave {
+    pub tiles: Vec<Tile>,
+    pub weight: f64, // weights of all leaves sum to 1
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RackInference {
+    pub leaves: Vec<InferredLeave>, // most likely first, empty when nothing is known
+}
+
+impl RackInference {
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    // a leave picked by weight
+    pub fn sample(&self) -> Option<&[Tile]> {
+        let mut target = rand::rng().random_range(0.0..1.0);
+        for leave in &self.leaves {
+            if target < leave.weight {
+                return Some(&leave.tiles);
+            }
+            target -= leave.weight;
+        }
+        self.leaves.last().map(|leave| leave.tiles.as_slice())
+    }
+
+    /// How likely a rack is next to a uniformly drawn one: the weight of every inferred leave it holds.
+    /// 1 when nothing is known, so it can scale weights either way.
+    pub fn likelihood(&self, rack: &[Tile]) -> f64 {
+        if self.leaves.is_empty() {
+            return 1.0;
+        }
+        let mut counts = [0u8; MAX_TILE_TYPES];
+        for tile in rack {
+            counts[tile.to_index() as usize] += 1;
+        }
+        self.leaves
+            .iter()
+            .filter(|leave| {
+                let mut left = counts;
+                leave.tiles.iter().all(|tile| {
+                    let count = &mut left[tile.to_index() as usize];
+                    let held = *count > 0;
+                    *count = count.saturating_sub(1);
+                    held
+                })
+            })
+            .map(|leave| leave.weight)
+            .sum()
+    }
+}
+
+// `count` tiles drawn from `pool` without putting them back
+fn draw_from(pool: &[u8; MAX_TILE_TYPES], count: usize) -> Vec<Tile> {
+    let mut pool = *pool;
+    let mut rng = rand::rng();
+    let mut tiles = Vec::with_capacity(count);
+    for _ in 0..count {
+        let total: usize = pool.iter().map(|&count| count as usize).sum();
+        if total == 0 {
+            break;
+        }
+        let mut target = rng.random_range(0..total);
+        for (index, count) in pool.iter_mut().enumerate() {
+            if target < *count as usize {
+                *count -= 1;
+                tiles.push(Tile::from_index(index as u8));
+                break;
+            }
+            target -= *count as usize;
+        }
+    }
+    tiles
+}
+
+/// Infers the leave the opponent kept with `action`. `before` is the game as it was when they took it,
+/// with them to move, and `unseen` the tiles the observer can't see now, their current rack included.
+/// An exchange only gives away how many tiles went back, so only the count of `Action::Swap` is used.
+pub fn infer_leave(before: &Game, action: &Action, unseen: &[u8; MAX_TILE_TYPES], evaluator: &Evaluator, config: &InferenceConfig) -> RackInference {
+    let alphabet = before.gaddag.alphabet();
+    let rack_size = before.racks[before.current_player].tiles().len();
+    // the rack tiles of a play, blanks back to undesignated like they were on the rack
+    let played: Vec<Tile> = match action {
+        Action::Move(mv) => mv
+            .tiles_data
+            .iter()
+            .filter_map(|played_tile| match played_tile {
+                PlayedTile::Rack(tile) if tile.is_blank() => Some(Tile::blank(None)),
+                PlayedTile::Rack(tile) => Some(*tile),
+                PlayedTile::Board(_) => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let leave_size = match action {
+        Action::Move(_) => rack_size.saturating_sub(played.len()),
+        Action::Swap(tiles) => rack_size.saturating_sub(tiles.len()),
+        Action::Pass => rack_size,
+    };
+    if leave_size == 0 {
+        return RackInference::default();
+    }
+
+    let mut weights: HashMap<LeaveKey, (Vec<Tile>, f64)> = HashMap::new();
+    for _ in 0..config.samples {
+        let leave = draw_from(unseen, leave_size);
+        if leave.len() < leave_size {
+            break;
+        }
+
+        let weight = match action {
+            // what was thrown back is unknown, so there's no rack to find the best turn for.
+            // the leave counts for what it's worth instead, players keep the tiles they like
+            Action::Swap(_) => ((evaluator.leave_value(&leave, before.bag.len(), alphabet) / config.temperature) as f64).exp(),
+            _ => {
+                let mut rack_tiles = played.clone();
+                rack_tiles.extend(&leave);
+                let rack = Rack::new(rack_tiles);
+                let generator = MoveGenerator {
+                    board: before.board.clone(),
+                    rack: rack.clone(),
+                };
+                let ranked = evaluator.rank(&generator, before.bag.len());
+                let best =
+                    ranked
+                        .first()
+                        .map_or(f32::MIN, |ranked| ranked.equity)
+                        .max(evaluator.leave_value(rack.tiles(), before.bag.len(), alphabet));
+                let taken = match action {
+                    Action::Move(mv) => evaluator.evaluate(mv, &before.board, &rack, before.bag.len(), alphabet),
+                    _ => evaluator.leave_value(rack.tiles(), before.bag.len(), alphabet),
+                };
+                (-((best - taken).max(0.0) / config.temperature) as f64).exp()
+            }
+        };
+
+        weights.entry(leave_key(&leave)).or_insert_with(|| (leave, 0.0)).1 += weight;
+    }
+
+    let total: f64 = weights.values().map(|(_, weight)| weight).sum();
+    if total == 0.0 {
+        return RackInference::default();
+    }
+    let mut leaves: Vec<InferredLeave> = weights
+        .into_values()
+        .map(|(tiles, weight)| InferredLeave {
+            tiles,
+            weight: weight / total,
+        })
+        .collect();
+    leaves.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    RackInference { leaves }
+}
+
+/// Deals `player` a rack holding a leave picked from `inference`, filled up from the bag.
+/// Falls back to a plain redeal when there's nothing inferred or the leave isn't in the unseen tiles anymore.
+pub fn deal_inferred(game: &mut Game, player: usize, inference: &RackInference) {
+    let size = game.racks[player].tiles().len();
+    for tile in game.racks[player].tiles() {
+        game.bag.tiles[tile.to_index() as usize] += 1;
+    }
+    game.racks[player] = Rack::new(Vec::new());
+
+    let leave = inference.sample().filter(|leave| leave.len() <= size).unwrap_or(&[]);
+    let mut bag = game.bag.clone();
+    let available = leave.iter().all(|tile| {
+        let count = &mut bag.tiles[tile.to_index() as usize];
+        let held = *count > 0;
+        *count = count.saturating_sub(1);
+        held
+    });
+    if !available {
+        game.racks[player] = Rack::new(game.bag.draw_tiles(size));
+        return;
+    }
+
+    let mut tiles = leave.to_vec();
+    tiles.extend(bag.draw_tiles(size - leave.len()));
+    game.bag = bag;
+    game.racks[player] = Rack::new(tiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Direction, Pos,
+        engine::{
+            anchors::AnchoredBoard,
+            testing::{gaddag, place_word, rack},
+        },
+        game::board::Board,
+    };
+
+    // CAT up against the right edge, so an S makes CATS on the triple word for 18
+    fn hook() -> Game {
+        let gaddag = gaddag();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 11), Direction::Horizontal);
+        let mut game = Game::init(gaddag.clone());
+        game.board = AnchoredBoard::new(board, gaddag);
+        game
+    }
+
+    fn pool(letters: &str) -> [u8; MAX_TILE_TYPES] {
+        let mut unseen = [0; MAX_TILE_TYPES];
+        for tile in rack(letters).tiles() {
+            unseen[tile.to_index() as usize] += 1;
+        }
+        unseen
+    }
+
+    fn weight(inference: &RackInference, letters: &str) -> f64 {
+        let tiles = rack(letters).tiles().to_vec();
+        inference
+            .leaves
+            .iter()
+            .find(|leave| leave.tiles == tiles)
+            .map_or(0.0, |leave| leave.weight)
+    }
+
+    #[test]
+    fn passing_up_a_hook_means_no_s() {
+        // one tile kept, an S or a V. with the S a pass gave up 18 for CATS, with the V there was nothing to play
+        let mut game = hook();
+        game.racks[0] = rack("V");
+        let inference = infer_leave(&game, &Action::Pass, &pool("SV"), &Evaluator::default(), &InferenceConfig::default());
+        assert!(weight(&inference, "S") < 0.2);
+        assert!(weight(&inference, "V") > 0.8);
+    }
+
+    #[test]
+    fn small_play_over_a_hook_means_no_s() {
+        // AT down from the A for 3 with one tile kept, when an S would've made CATS for 18
+        let mut game = hook();
+        let alphabet = game.gaddag.alphabet().clone();
+        let generator = MoveGenerator {
+            board: game.board.clone(),
+            rack: rack("T"),
+        };
+        let at = generator
+            .generate_moves()
+            .into_iter()
+            .find(|mv| mv.get_word_string(&alphabet) == "AT" && matches!(mv.direction, Direction::Vertical))
+            .unwrap();
+        game.racks[0] = rack("TV");
+        let inference = infer_leave(&game, &Action::Move(at), &pool("SV"), &Evaluator::default(), &InferenceConfig::default());
+        assert!(weight(&inference, "S") < weight(&inference, "V") / 2.0);
+    }
+
+    #[test]
+    fn swaps_keep_the_better_leave() {
+        let game = Game::init(gaddag());
+        let mut unseen = [0; MAX_TILE_TYPES];
+        for tile in rack("?QVV").tiles() {
+            unseen[tile.to_index() as usize] += 1;
+        }
+        // five of seven went back, two kept out of ?QVV
+        let action = Action::Swap(rack("ABCDE").tiles().to_vec());
+        let inference = infer_leave(&game, &action, &unseen, &Evaluator::default(), &InferenceConfig::default());
+        assert!(inference.leaves[0].tiles.iter().any(|tile| tile.is_blank()));
+    }
+
+    #[test]
+    fn likelihood_counts_the_leaves_a_rack_holds() {
+        let inference = RackInference {
+            leaves: vec![
+                InferredLeave {
+                    tiles: rack("S").tiles().to_vec(),
+                    weight: 0.75,
+                },
+                InferredLeave {
+                    tiles: rack("VV").tiles().to_vec(),
+                    weight: 0.25,
+                },
+            ],
+        };
+        assert_eq!(inference.likelihood(rack("SAB").tiles()), 0.75);
+        assert_eq!(inference.likelihood(rack("SVV").tiles()), 1.0);
+        assert_eq!(inference.likelihood(rack("SV").tiles()), 0.75);
+        assert_eq!(inference.likelihood(rack("AB").tiles()), 0.0);
+        assert_eq!(RackInference::default().likelihood(rack("AB").tiles()), 1.0);
+    }
+
+    #[test]
+    fn deals_the_leave_or_redeals_without_it() {
+        let inference = RackInference {
+            leaves: vec![InferredLeave {
+                tiles: rack("Z").tiles().to_vec(),
+                weight: 1.0,
+            }],
+        };
+        let z = Tile::letter(b'Z');
+        let total = |game: &Game| game.bag.len() + game.racks[0].tiles().len();
+
+        // the other rack back in the bag, so the Z isn't on it
+        let mut game = Game::init(gaddag());
+        for &tile in game.racks[1].tiles() {
+            game.bag.tiles[tile.to_index() as usize] += 1;
+        }
+        game.racks[1] = Rack::new(Vec::new());
+        let before = total(&game);
+        deal_inferred(&mut game, 0, &inference);
+        assert!(game.racks[0].tiles().contains(&z));
+        assert_eq!(game.racks[0].tiles().len(), 7);
+        assert_eq!(total(&game), before);
+
+        // the only Z is gone, so it's a plain redeal
+        let mut game = Game::init(gaddag());
+        game.racks[0].remove_tile(z);
+        game.bag.tiles[z.to_index() as usize] = 0;
+        let before = total(&game);
+        let size = game.racks[0].tiles().len();
+        deal_inferred(&mut game, 0, &inference);
+        assert!(!game.racks[0].tiles().contains(&z));
+        assert_eq!(game.racks[0].tiles().len(), size);
+        assert_eq!(total(&game), before);
+    }
+}
@@ -0,0 +1,324 @@
+use super::moves::{CandidateMove, Move, MoveGenerator, PlayedTile};
+use crate::game::{
+    alphabet::Alphabet,
+    rack::{RACK_TILES, Rack},
+    tile::Tile,
+};
+use smallvec::SmallVec;
+use std::{collections::HashMap, fmt, ops::ControlFlow, path::Path};
+
+// a leave as sorted Tile::to_index values, blanks (0) first. same order and numbering as klv files use
+pub type LeaveKey = SmallVec<[u8; RACK_TILES]>;
+
+#[derive(Debug)]
+pub enum LeaveError {
+    Io(std::io::Error),
+    Truncated,
+    Corrupt(String),
+    UnknownLetters(String),
+}
+
+impl fmt::Display for LeaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeaveError::Io(err) => write!(f, "leave table io error: {}", err),
+            LeaveError::Truncated => write!(f, "leave table is truncated"),
+            LeaveError::Corrupt(err) => write!(f, "leave table is corrupt: {}", err),
+            LeaveError::UnknownLetters(leave) => write!(f, "{:?} is not made of tiles of the alphabet", leave),
+        }
+    }
+}
+
+impl std::error::Error for LeaveError {}
+
+impl From<std::io::Error> for LeaveError {
+    fn from(err: std::io::Error) -> Self {
+        LeaveError::Io(err)
+    }
+}
+
+pub fn leave_key(tiles: &[Tile]) -> LeaveKey {
+    let mut key: LeaveKey = tiles.iter().map(|tile| tile.to_index()).collect();
+    key.sort_unstable();
+    key
+}
+
+/// Values of the tiles kept after a move, in points. Leaves that aren't in the table are worth 0.
+#[derive(Debug, Clone, Default)]
+pub struct LeaveTable {
+    pub values: HashMap<LeaveKey, f32>,
+}
+
+impl LeaveTable {
+    // .klv files are read as klv, anything else as csv
+    pub fn load(path: &str, alphabet: &Alphabet) -> Result<Self, LeaveError> {
+        let is_klv = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("klv"));
+        if is_klv {
+            LeaveTable::from_klv(&std::fs::read(path)?)
+        } else {
+            LeaveTable::from_csv(&std::fs::read_to_string(path)?, alphabet)
+        }
+    }
+
+    /// Macondo/Wolges klv (klv1 or klv2): a kwg with every leave as a word, then one value per leave in kwg word order.
+    /// Tiles in the kwg are 0 for the blank and alphabet index + 1 for letters, the same as `Tile::to_index`.
+    pub fn from_klv(bytes: &[u8]) -> Result<Self, LeaveError> {
+        let read_u32 = |at: usize| -> Result<u32, LeaveError> {
+            let chunk = bytes.get(at..at + 4).ok_or(LeaveError::Truncated)?;
+            Ok(u32::from_le_bytes(chunk.try_into().unwrap()))
+        };
+
+        let node_count = read_u32(0)? as usize;
+        let nodes: Vec<u32> = (0..node_count).map(|i| read_u32(4 + i * 4)).collect::<Result<_, _>>()?;
+        let values_at = 4 + node_count * 4;
+        let leave_count = read_u32(values_at)? as usize;
+
+        // klv2 stores f32s, the older klv1 i16s in 1/256ths of a point. the size left tells them apart
+        let rest = &bytes[values_at + 4..];
+        let values: Vec<f32> = if rest.len() == leave_count * 4 {
+            rest.chunks_exact(4).map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap())).collect()
+        } else if rest.len() == leave_count * 2 {
+            rest.chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes(chunk.try_into().unwrap()) as f32 / 256.0)
+                .collect()
+        } else {
+            return Err(LeaveError::Corrupt(format!("{} bytes of values for {} leaves", rest.len(), leave_count)));
+        };
+
+        let mut leaves = Vec::with_capacity(leave_count);
+        if let Some(&root) = nodes.first() {
+            let mut prefix = LeaveKey::new();
+            walk_kwg(&nodes, (root & KWG_ARC_INDEX) as usize, &mut prefix, &mut leaves, 0)?;
+        }
+        if leaves.len() != leave_count {
+            return Err(LeaveError::Corrupt(format!(
+                "{} leaves in the kwg but {} values",
+                leaves.len(),
+                leave_count
+            )));
+        }
+
+        Ok(LeaveTable {
+            values: leaves.into_iter().zip(values).collect(),
+        })
+    }
+
+    // "leave,value" lines like "?ERS,24.5", ? for the blank. a header line is skipped
+    pub fn from_csv(text: &str, alphabet: &Alphabet) -> Result<Self, LeaveError> {
+        let mut values = HashMap::new();
+        for (i, line) in text.lines().map(str::trim).filter(|line| !line.is_empty()).enumerate() {
+            let Some((leave, value)) = line.split_once(',') else {
+                return Err(LeaveError::Corrupt(format!("bad leave line {:?}", line)));
+            };
+            let Ok(value) = value.trim().parse::<f32>() else {
+                if i == 0 {
+                    continue;
+                }
+                return Err(LeaveError::Corrupt(format!("bad leave value in {:?}", line)));
+            };
+            let tiles = alphabet
+                .parse_tiles(&leave.trim().to_uppercase())
+                .ok_or_else(|| LeaveError::UnknownLetters(leave.to_string()))?;
+            values.insert(leave_key(&tiles), value);
+        }
+        Ok(LeaveTable { values })
+    }
+
+    // same format from_csv reads, shortest leaves first
+    pub fn to_csv(&self, alphabet: &Alphabet) -> String {
+        let mut leaves: Vec<(&LeaveKey, &f32)> = self.values.iter().collect();
+        leaves.sort_unstable_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(b.0)));
+        let mut csv = String::from("leave,value\n");
+        for (key, value) in leaves {
+            let leave: String = key.iter().map(|&index| alphabet.label(Tile::from_index(index))).collect();
+            csv.push_str(&format!("{},{}\n", leave, value));
+        }
+        csv
+    }
+
+    pub fn save_csv(&self, path: &str, alphabet: &Alphabet) -> Result<(), LeaveError> {
+        std::fs::write(path, self.to_csv(alphabet))?;
+        Ok(())
+    }
+
+    pub fn value(&self, leave: &[Tile]) -> f32 {
+        if leave.is_empty() {
+            return 0.0;
+        }
+        self.values.get(&leave_key(leave)).copied().unwrap_or(0.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+const KWG_TILE_SHIFT: u32 = 24;
+const KWG_ACCEPTS: u32 = 0x800000;
+const KWG_IS_END: u32 = 0x400000;
+const KWG_ARC_INDEX: u32 = 0x3fffff;
+
+// words of the kwg in order, which is the order the klv values are in
+fn walk_kwg(nodes: &[u32], mut idx: usize, prefix: &mut LeaveKey, leaves: &mut Vec<LeaveKey>, depth: usize) -> Result<(), LeaveError> {
+    if idx == 0 {
+        return Ok(());
+    }
+    if depth > RACK_TILES {
+        return Err(LeaveError::Corrupt("leave longer than a rack".to_string()));
+    }
+    loop {
+        let &node = nodes
+            .get(idx)
+            .ok_or_else(|| LeaveError::Corrupt(format!("arc to node {} out of range", idx)))?;
+        prefix.push((node >> KWG_TILE_SHIFT) as u8);
+        if node & KWG_ACCEPTS != 0 {
+            leaves.push(prefix.clone());
+        }
+        walk_kwg(nodes, (node & KWG_ARC_INDEX) as usize, prefix, leaves, depth + 1)?;
+        prefix.pop();
+        if node & KWG_IS_END != 0 {
+            return Ok(());
+        }
+        idx += 1;
+    }
+}
+
+// the rack tiles a move keeps
+pub fn leave_after(rack: &Rack, tiles: impl Iterator<Item = PlayedTile>) -> Rack {
+    let mut leave = rack.clone();
+    for played_tile in tiles {
+        if let PlayedTile::Rack(tile) = played_tile {
+            leave.remove_tile(tile);
+        }
+    }
+    leave
+}
+
+impl Move {
+    pub fn leave(&self, rack: &Rack) -> Rack {
+        leave_after(rack, self.tiles_data.iter().copied())
+    }
+}
+
+// a move with what its leave is worth. equity = score + leave
+#[derive(Debug, Clone)]
+pub struct RankedMove {
+    pub mv: Move,
+    pub leave: f32,
+    pub equity: f32,
+}
+
+impl MoveGenerator {
+    /// The `n` best moves by equity (score + leave value against the generator's rack), best first.
+    /// Equal equities keep `generate_moves` order.
+    pub fn top_moves_by_equity(&self, n: usize, leaves: &LeaveTable) -> Vec<RankedMove> {
+        // kept sorted, best first. n is small next to the number of moves
+        let mut best: Vec<RankedMove> = Vec::with_capacity(n + 1);
+        let _ = self.visit_moves(&mut |candidate: &CandidateMove| {
+            let leave = leaves.value(leave_after(&self.rack, candidate.tiles()).tiles());
+            let equity = candidate.score() as f32 + leave;
+            if best.len() < n || best.last().is_some_and(|worst| equity > worst.equity) {
+                let at = best.partition_point(|ranked| ranked.equity >= equity);
+                best.insert(
+                    at,
+                    RankedMove {
+                        mv: candidate.to_move(),
+                        leave,
+                        equity,
+                    },
+                );
+                best.truncate(n);
+            }
+            ControlFlow::Continue(())
+        });
+        best
+    }
+
+    // every move, best equity first
+    pub fn ranked_moves(&self, leaves: &LeaveTable) -> Vec<RankedMove> {
+        let mut ranked: Vec<RankedMove> = self
+            .generate_moves()
+            .into_iter()
+            .map(|mv| {
+                let leave = leaves.value(mv.leave(&self.rack).tiles());
+                RankedMove {
+                    equity: mv.score as f32 + leave,
+                    leave,
+                    mv,
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.equity.total_cmp(&a.equity));
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a kwg holding ?, ?A, A and AB (in that order), then their values
+    fn klv(values: &[u8]) -> Vec<u8> {
+        let nodes: [u32; 6] = [
+            KWG_IS_END | 2, // dawg root
+            KWG_IS_END,     // gaddag root, klv files leave it empty
+            KWG_ACCEPTS | 4,
+            1 << KWG_TILE_SHIFT | KWG_ACCEPTS | KWG_IS_END | 5,
+            1 << KWG_TILE_SHIFT | KWG_ACCEPTS | KWG_IS_END,
+            2 << KWG_TILE_SHIFT | KWG_ACCEPTS | KWG_IS_END,
+        ];
+        let mut bytes = (nodes.len() as u32).to_le_bytes().to_vec();
+        for node in nodes {
+            bytes.extend(node.to_le_bytes());
+        }
+        bytes.extend(4u32.to_le_bytes());
+        bytes.extend(values);
+        bytes
+    }
+
+    fn key(indices: &[u8]) -> LeaveKey {
+        indices.iter().copied().collect()
+    }
+
+    #[test]
+    fn reads_klv2_and_klv1() {
+        let values: Vec<u8> = [25.0f32, 30.5, 1.0, -2.25].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let table = LeaveTable::from_klv(&klv(&values)).unwrap();
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.values[&key(&[0])], 25.0);
+        assert_eq!(table.values[&key(&[0, 1])], 30.5);
+        assert_eq!(table.values[&key(&[1])], 1.0);
+        assert_eq!(table.values[&key(&[1, 2])], -2.25);
+
+        let values: Vec<u8> = [6400i16, 7808, 256, -576].iter().flat_map(|value| value.to_le_bytes()).collect();
+        let klv1 = LeaveTable::from_klv(&klv(&values)).unwrap();
+        assert_eq!(klv1.values, table.values);
+
+        assert!(matches!(LeaveTable::from_klv(&klv(&values[..6])), Err(LeaveError::Corrupt(_))));
+        assert!(matches!(LeaveTable::from_klv(&[6, 0, 0]), Err(LeaveError::Truncated)));
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let alphabet = Alphabet::english();
+        let table = LeaveTable::from_csv("leave,value\n?ERS,24.5\nq,-7\nEE,-3.25\n", &alphabet).unwrap();
+        assert_eq!(
+            table.value(&[Tile::letter(b'S'), Tile::blank(None), Tile::letter(b'R'), Tile::letter(b'E')]),
+            24.5
+        );
+        assert_eq!(table.value(&[Tile::letter(b'Q')]), -7.0);
+
+        let klv_values: Vec<u8> = [25.0f32, 30.5, 1.0, -2.25].iter().flat_map(|value| value.to_le_bytes()).collect();
+        for table in [table, LeaveTable::from_klv(&klv(&klv_values)).unwrap()] {
+            let csv = table.to_csv(&alphabet);
+            assert_eq!(LeaveTable::from_csv(&csv, &alphabet).unwrap().values, table.values);
+        }
+
+        assert!(matches!(LeaveTable::from_csv("A,1\nB", &alphabet), Err(LeaveError::Corrupt(_))));
+        assert!(matches!(LeaveTable::from_csv("A1,1", &alphabet), Err(LeaveError::UnknownLetters(_))));
+    }
+}
@@ -0,0 +1,65 @@
+use super::gaddag::Gaddag;
+use crate::game::alphabet::Alphabet;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+// named lexica loaded side by side, e.g. "CSW24" and "NWL23".
+// each one is shared as an Arc<Gaddag> so games and generators can hold on to it
+#[derive(Debug)]
+pub struct Lexicons {
+    dir: PathBuf,
+    loaded: HashMap<String, Arc<Gaddag>>,
+}
+
+impl Lexicons {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Lexicons {
+            dir: dir.into(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    // english lexicon, see load_with_alphabet
+    pub fn load(&mut self, name: &str) -> Result<Arc<Gaddag>, Box<dyn std::error::Error>> {
+        self.load_with_alphabet(name, &Alphabet::english())
+    }
+
+    // loads `{dir}/{name}.fst`, rebuilding it from `{dir}/{name}.txt` when that or the alphabet changed since the fst was built
+    pub fn load_with_alphabet(&mut self, name: &str, alphabet: &Alphabet) -> Result<Arc<Gaddag>, Box<dyn std::error::Error>> {
+        if let Some(gaddag) = self.loaded.get(name) {
+            return Ok(gaddag.clone());
+        }
+
+        let fst_path = self.dir.join(format!("{}.fst", name));
+        let txt_path = self.dir.join(format!("{}.txt", name));
+        if !fst_path.exists() && !txt_path.exists() {
+            return Err(format!("no wordlist found for lexicon {}", name).into());
+        }
+
+        let gaddag = Gaddag::load_or_build(&fst_path.to_string_lossy(), &txt_path.to_string_lossy(), alphabet)?;
+        Ok(self.insert(name, gaddag))
+    }
+
+    pub fn insert(&mut self, name: &str, gaddag: Gaddag) -> Arc<Gaddag> {
+        let gaddag = Arc::new(gaddag);
+        self.loaded.insert(name.to_string(), gaddag.clone());
+        gaddag
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Gaddag>> {
+        self.loaded.get(name).cloned()
+    }
+
+    pub fn unload(&mut self, name: &str) -> Option<Arc<Gaddag>> {
+        self.loaded.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.loaded.keys().map(|name| name.as_str())
+    }
+}
+
+impl Default for Lexicons {
+    fn default() -> Self {
+        Lexicons::new("wordlists")
+    }
+}
@@ -1,3 +1,21 @@
+pub mod actions;
 pub mod anchors;
+pub mod dawg;
+pub mod diff;
+pub mod endgame;
+pub mod evaluator;
 pub mod gaddag;
+pub mod hooks;
+pub mod inference;
+pub mod leaves;
+pub mod lexicon;
 pub mod moves;
+pub mod preendgame;
+pub mod probability;
+pub mod search;
+pub mod simulation;
+pub mod superleaves;
+#[cfg(test)]
+mod testing;
+pub mod validate;
+pub mod words;
@@ -1,15 +1,31 @@
-use super::anchors::CrossChecks;
+use super::anchors::{AnchoredBoard, CrossChecks};
+use super::gaddag::Gaddag;
 use crate::Direction;
-use crate::game::board::Multiplier;
+use crate::game::{alphabet::Alphabet, board::Multiplier};
 use crate::{
-    GADDAG, Pos,
+    Pos,
     game::{
         board::{BOARD_SIZE, Board},
-        rack::Rack,
+        rack::{RACK_TILES, Rack},
         tile::Tile,
     },
 };
 use smallvec::SmallVec;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    hash::{Hash, Hasher},
+    ops::ControlFlow,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    },
+};
+
+// what makes two moves the same play: the rack tiles put down, where, and what the blanks stand for.
+// board tiles, direction and score follow from those
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MoveKey(pub SmallVec<[(Pos, Tile); 7]>); // sorted by position
 
 #[derive(Debug, Clone)]
 pub struct Move {
@@ -30,8 +46,52 @@ impl Move {
         })
     }
 
-    pub fn get_word_string(&self) -> String {
-        self.tiles_data.iter().map(|tile| tile.byte() as char).collect()
+    // tiles the move takes off the rack
+    pub fn tiles_placed(&self) -> usize {
+        self.tiles_data.iter().filter(|tile| matches!(tile, PlayedTile::Rack(_))).count()
+    }
+
+    pub fn get_word_string(&self, alphabet: &Alphabet) -> String {
+        self.tiles_data.iter().map(|tile| alphabet.label(tile.tile())).collect()
+    }
+
+    pub fn key(&self) -> MoveKey {
+        let mut placed: SmallVec<[(Pos, Tile); 7]> = self
+            .tile_positions()
+            .filter_map(|(pos, played_tile)| match played_tile {
+                PlayedTile::Rack(tile) => Some((pos, tile)),
+                PlayedTile::Board(_) => None,
+            })
+            .collect();
+        placed.sort_unstable();
+        MoveKey(placed)
+    }
+}
+
+// moves compare by key, so a single tile reads the same whichever way it was found
+impl PartialEq for Move {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Move {}
+
+impl Hash for Move {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for Move {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Move {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
     }
 }
 
@@ -48,12 +108,16 @@ pub enum PlayedTile {
 }
 
 impl PlayedTile {
-    fn byte(&self) -> u8 {
+    pub fn tile(&self) -> Tile {
         match self {
-            PlayedTile::Rack(tile) => tile.byte(),
-            PlayedTile::Board(tile) => tile.byte(),
+            PlayedTile::Rack(tile) => *tile,
+            PlayedTile::Board(tile) => *tile,
         }
     }
+
+    fn byte(&self) -> u8 {
+        self.tile().byte()
+    }
 }
 
 struct MoveBuffer {
@@ -91,7 +155,7 @@ impl MoveBuffer {
         self.played_tiles_count > 0
     }
 
-    fn calculate_score(&self, board: &Board, direction: Direction, cross_checks: &CrossChecks, anchor_pos: Pos) -> u16 {
+    fn calculate_score(&self, board: &Board, direction: Direction, cross_checks: &CrossChecks, anchor_pos: Pos, alphabet: &Alphabet) -> u16 {
         let mut main_score = 0u16;
         let mut word_multiplier = 1u16;
         let mut cross_scores = 0u16;
@@ -108,12 +172,12 @@ impl MoveBuffer {
 
             if let Some(played_tile) = self.data[i] {
                 if let PlayedTile::Board(tile) = played_tile {
-                    main_score += tile.points() as u16;
+                    main_score += alphabet.points(tile) as u16;
                     continue;
                 }
 
                 if let PlayedTile::Rack(tile) = played_tile {
-                    let letter_score = tile.points() as u16;
+                    let letter_score = alphabet.points(tile) as u16;
 
                     main_score += match board.get_multiplier(pos) {
                         Some(Multiplier::DoubleLetter) => letter_score * 2,
@@ -130,8 +194,8 @@ impl MoveBuffer {
                     };
 
                     let cross_check = unsafe { *cross_checks.get_unchecked(pos.row).get_unchecked(pos.col) };
-                    let cross_score = cross_check.score() as u16;
-                    if cross_score > 0 {
+                    let cross_score = cross_check.score();
+                    if cross_check.has_word() {
                         cross_scores += match board.get_multiplier(pos) {
                             Some(Multiplier::DoubleLetter) => cross_score + 2 * letter_score,
                             Some(Multiplier::TripleLetter) => cross_score + 3 * letter_score,
@@ -149,32 +213,116 @@ impl MoveBuffer {
     }
 }
 
+// a move as the generator finds it, borrowed from its buffer. nothing is scored or allocated until asked for
+pub struct CandidateMove<'a> {
+    generator: &'a MoveGenerator,
+    move_buffer: &'a MoveBuffer,
+    direction: Direction,
+    anchor_pos: Pos,
+    cross_checks: &'a CrossChecks,
+}
+
+impl CandidateMove<'_> {
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // where the word starts
+    pub fn pos(&self) -> Pos {
+        let word_start_idx = self.move_buffer.data.iter().position(|tile| tile.is_some()).unwrap_or(0);
+        match self.direction {
+            Direction::Horizontal => Pos::new(self.anchor_pos.row, word_start_idx),
+            Direction::Vertical => Pos::new(word_start_idx, self.anchor_pos.col),
+        }
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = PlayedTile> + '_ {
+        self.move_buffer.data.iter().filter_map(|&tile| tile)
+    }
+
+    pub fn tiles_placed(&self) -> usize {
+        self.move_buffer.played_tiles_count as usize
+    }
+
+    pub fn is_bingo(&self) -> bool {
+        self.tiles_placed() == RACK_TILES
+    }
+
+    pub fn score(&self) -> u16 {
+        let generator = self.generator;
+        self.move_buffer.calculate_score(
+            &generator.board,
+            self.direction,
+            self.cross_checks,
+            self.anchor_pos,
+            generator.gaddag().alphabet(),
+        )
+    }
+
+    pub fn to_move(&self) -> Move {
+        Move {
+            tiles_data: self.tiles().collect(),
+            pos: self.pos(),
+            direction: self.direction,
+            score: self.score(),
+        }
+    }
+}
+
+/// Gets every move from `MoveGenerator::visit_moves`. Break to stop the search there.
+/// Closures taking a `&CandidateMove` are visitors too.
+pub trait MoveVisitor {
+    fn visit(&mut self, candidate: &CandidateMove) -> ControlFlow<()>;
+
+    // false skips the anchor in that direction, none of its moves are searched
+    fn enter_anchor(&mut self, _direction: Direction, _anchor_pos: Pos) -> bool {
+        true
+    }
+}
+
+impl<F: FnMut(&CandidateMove) -> ControlFlow<()>> MoveVisitor for F {
+    fn visit(&mut self, candidate: &CandidateMove) -> ControlFlow<()> {
+        self(candidate)
+    }
+}
+
 // per-turn fields in this guy
 pub struct MoveGenerator {
-    pub board: Board,
+    pub board: AnchoredBoard, // anchors and cross checks come with the board, for its lexicon
     pub rack: Rack,
 }
 
 impl MoveGenerator {
-    pub fn run(board: Board, rack: Rack) -> Vec<Move> {
-        let generator = MoveGenerator { board, rack };
+    // computes anchors and cross checks from scratch, keep an AnchoredBoard around to avoid that
+    pub fn run(board: Board, rack: Rack, gaddag: Arc<Gaddag>) -> Vec<Move> {
+        let generator = MoveGenerator {
+            board: AnchoredBoard::new(board, gaddag),
+            rack,
+        };
         generator.generate_moves()
     }
 
-    // we start from a board, with an otherwise empty slate
-    pub fn generate_moves(&self) -> Vec<Move> {
-        let (h_anchors, h_allowed) = super::anchors::find_anchors(&self.board, &Direction::Horizontal);
-        let (v_anchors, v_allowed) = super::anchors::find_anchors(&self.board, &Direction::Vertical);
+    fn gaddag(&self) -> &Gaddag {
+        self.board.gaddag()
+    }
 
-        let mut moves = Vec::new();
-        let mut rack = self.rack.clone();
+    // one unit of work per direction and anchor, in output order
+    fn work_items(&self) -> Vec<(Direction, Pos)> {
+        [Direction::Horizontal, Direction::Vertical]
+            .into_iter()
+            .flat_map(|direction| self.board.anchors().map(move |anchor_pos| (direction, anchor_pos)))
+            .collect()
+    }
 
-        for anchor_pos in h_anchors {
-            self.check_anchors(Direction::Horizontal, &mut moves, &mut rack, anchor_pos, &v_allowed);
-        }
-        for anchor_pos in v_anchors {
-            self.check_anchors(Direction::Vertical, &mut moves, &mut rack, anchor_pos, &h_allowed);
-        }
+    /// Moves come out in a fixed order: all horizontal moves, then all vertical ones,
+    /// each grouped by anchor row by row, left to right, and within an anchor in gaddag (letter) order.
+    /// The same board and rack always give the same list.
+    pub fn generate_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let _ = self.visit_moves(&mut |candidate: &CandidateMove| {
+            moves.push(candidate.to_move());
+            ControlFlow::Continue(())
+        });
 
         // filter moves
         // use rand::Rng;
@@ -189,7 +337,111 @@ impl MoveGenerator {
         moves
     }
 
-    pub fn check_anchors(&self, direction: Direction, mut moves: &mut Vec<Move>, mut rack: &mut Rack, anchor_pos: Pos, cross_checks: &CrossChecks) {
+    /// Same moves in the same order as `generate_moves`, with the anchors spread over `threads` threads.
+    /// Threads pick up anchors one at a time so a few busy anchors don't hold up the rest,
+    /// and the results are put back in anchor order at the end.
+    pub fn generate_moves_parallel(&self, threads: usize) -> Vec<Move> {
+        let items = self.work_items();
+        let threads = threads.min(items.len());
+        if threads <= 1 {
+            return self.generate_moves();
+        }
+
+        let next_item = AtomicUsize::new(0);
+        let mut results: Vec<(usize, Vec<Move>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut rack = self.rack.clone();
+                        let mut done = Vec::new();
+                        loop {
+                            let idx = next_item.fetch_add(1, AtomicOrdering::Relaxed);
+                            let Some(&(direction, anchor_pos)) = items.get(idx) else {
+                                break;
+                            };
+                            let mut moves = Vec::new();
+                            let mut collect = |candidate: &CandidateMove| {
+                                moves.push(candidate.to_move());
+                                ControlFlow::Continue(())
+                            };
+                            let _ = self.check_anchors(direction, &mut collect, &mut rack, anchor_pos, self.board.cross_checks(direction));
+                            done.push((idx, moves));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        });
+
+        results.sort_unstable_by_key(|(idx, _)| *idx);
+        results.into_iter().flat_map(|(_, moves)| moves).collect()
+    }
+
+    /// Hands every move to the visitor in `generate_moves` order without building any of them.
+    /// Stops as soon as the visitor breaks, and returns what it returned.
+    pub fn visit_moves<V: MoveVisitor>(&self, visitor: &mut V) -> ControlFlow<()> {
+        let mut rack = self.rack.clone();
+        for (direction, anchor_pos) in self.work_items() {
+            if visitor.enter_anchor(direction, anchor_pos) {
+                self.check_anchors(direction, visitor, &mut rack, anchor_pos, self.board.cross_checks(direction))?;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn count_moves(&self) -> usize {
+        let mut count = 0;
+        let _ = self.visit_moves(&mut |_: &CandidateMove| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    pub fn has_bingo(&self) -> bool {
+        self.visit_moves(&mut |candidate: &CandidateMove| {
+            if candidate.is_bingo() {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .is_break()
+    }
+
+    /// The `k` highest scoring moves, best first. Equal scores keep `generate_moves` order.
+    /// Only moves that make it into the current top `k` are built.
+    pub fn top_moves(&self, k: usize) -> Vec<Move> {
+        if k == 0 {
+            return Vec::new();
+        }
+        // min-heap on (score, earlier first), so the root is the one to drop
+        let mut best: BinaryHeap<Reverse<(u16, Reverse<usize>, Move)>> = BinaryHeap::with_capacity(k + 1);
+        let mut seen = 0;
+        let _ = self.visit_moves(&mut |candidate: &CandidateMove| {
+            let score = candidate.score();
+            let keep = best.len() < k || best.peek().is_some_and(|Reverse((worst, _, _))| score > *worst);
+            if keep {
+                best.push(Reverse((score, Reverse(seen), candidate.to_move())));
+                if best.len() > k {
+                    best.pop();
+                }
+            }
+            seen += 1;
+            ControlFlow::Continue(())
+        });
+        best.into_sorted_vec().into_iter().map(|Reverse((_, _, mv))| mv).collect()
+    }
+
+    pub fn check_anchors<V: MoveVisitor>(
+        &self,
+        direction: Direction,
+        visitor: &mut V,
+        rack: &mut Rack,
+        anchor_pos: Pos,
+        cross_checks: &CrossChecks,
+    ) -> ControlFlow<()> {
         // before recursion, get suffix:
         // _ _ x R A I N _ -> RAIN
         let mut move_buffer = MoveBuffer::new();
@@ -219,37 +471,74 @@ impl MoveGenerator {
         };
 
         // we start from the suffix node, which will always be valid. hopefully.
-        let mut current_node = GADDAG.0.as_fst().root().addr();
+        let mut current_node = self.gaddag().root();
         for i in (0..BOARD_SIZE).rev() {
             if move_buffer.is_occupied(i) {
                 let byte = move_buffer.data[i].unwrap().byte();
-                let node = &GADDAG.0.as_fst().node(current_node);
+                let node = &self.gaddag().node_at(current_node);
                 if let Some(transition_idx) = node.find_input(byte) {
                     current_node = node.transition_addr(transition_idx);
                 } else {
-                    return;
+                    return ControlFlow::Continue(());
                 }
             }
         }
 
         self.explore(
-            &mut moves,
+            visitor,
             &mut move_buffer,
-            &mut rack,
+            rack,
             direction,
             anchor_pos,
-            &cross_checks,
+            cross_checks,
             suffix_offset,
             0,
             ExploreDir::Back,
             current_node,
-            false,
-        );
+        )
+    }
+
+    // the buffer is a word if the node is terminal, and a move if a rack tile is in it
+    fn record_move<V: MoveVisitor>(
+        &self,
+        visitor: &mut V,
+        move_buffer: &MoveBuffer,
+        direction: Direction,
+        anchor_pos: Pos,
+        cross_checks: &CrossChecks,
+        current_node: fst::raw::CompiledAddr,
+    ) -> ControlFlow<()> {
+        if !self.gaddag().is_terminal(current_node) || !move_buffer.has_played_tile() {
+            return ControlFlow::Continue(());
+        }
+
+        // a single tile that also makes a word across is found by the horizontal pass as well, keep that one
+        if matches!(direction, Direction::Vertical) && move_buffer.played_tiles_count == 1 {
+            let idx = move_buffer
+                .data
+                .iter()
+                .position(|tile| matches!(tile, Some(PlayedTile::Rack(_))))
+                .unwrap();
+            let pos = Pos::new(idx, anchor_pos.col);
+            let across = [pos.offset(0, -1), pos.offset(0, 1)];
+            if across.into_iter().flatten().any(|neighbor| self.board.is_occupied(neighbor)) {
+                return ControlFlow::Continue(());
+            }
+        }
+
+        visitor.visit(&CandidateMove {
+            generator: self,
+            move_buffer,
+            direction,
+            anchor_pos,
+            cross_checks,
+        })
     }
 
-    fn explore(
+    #[allow(clippy::too_many_arguments)] // recursive and hot, the state stays in registers instead of a struct
+    fn explore<V: MoveVisitor>(
         &self,
-        moves: &mut Vec<Move>,
+        visitor: &mut V,
         move_buffer: &mut MoveBuffer,
         rack: &mut Rack,
         direction: Direction,
@@ -259,8 +548,7 @@ impl MoveGenerator {
         offset: i8,
         explore_dir: ExploreDir,
         current_node: fst::raw::CompiledAddr,
-        placed_tiles_seen: bool,
-    ) {
+    ) -> ControlFlow<()> {
         let offset_dir = match direction {
             Direction::Horizontal => (0, 1),
             Direction::Vertical => (1, 0),
@@ -269,16 +557,19 @@ impl MoveGenerator {
         let current_pos = match anchor_pos.offset(offset_dir.0 * offset as isize, offset_dir.1 * offset as isize) {
             Some(pos) => pos,
             None => {
+                // off the board counts as an empty square: the word can end here
+                self.record_move(visitor, move_buffer, direction, anchor_pos, cross_checks, current_node)?;
+
                 // flip direction
                 if matches!(explore_dir, ExploreDir::Back) {
-                    let new_node = if let Some(delimiter_node) = GADDAG.can_next(current_node, super::gaddag::DELIMITER) {
+                    let new_node = if let Some(delimiter_node) = self.gaddag().can_next(current_node, super::gaddag::DELIMITER) {
                         delimiter_node
                     } else {
-                        return;
+                        return ControlFlow::Continue(());
                     };
 
-                    self.explore(
-                        moves,
+                    return self.explore(
+                        visitor,
                         move_buffer,
                         rack,
                         direction,
@@ -288,10 +579,9 @@ impl MoveGenerator {
                         suffix_offset as i8, // start at 1 to the right
                         ExploreDir::Forward,
                         new_node,
-                        placed_tiles_seen,
                     );
                 }
-                return;
+                return ControlFlow::Continue(());
             }
         };
 
@@ -301,11 +591,11 @@ impl MoveGenerator {
         };
 
         if let Some(tile) = self.board.get_board_tile(current_pos) {
-            if let Some(next_node) = GADDAG.can_next(current_node, tile.byte()) {
+            if let Some(next_node) = self.gaddag().can_next(current_node, tile.byte()) {
                 move_buffer.set(board_idx, PlayedTile::Board(tile));
 
-                self.explore(
-                    moves,
+                let flow = self.explore(
+                    visitor,
                     move_buffer,
                     rack,
                     direction,
@@ -315,42 +605,33 @@ impl MoveGenerator {
                     offset + explore_dir as i8,
                     explore_dir,
                     next_node,
-                    true, // placed tiles seen
                 );
 
                 move_buffer.unset(board_idx);
+                return flow;
             }
-            return;
+            return ControlFlow::Continue(());
         }
 
-        if GADDAG.is_terminal(current_node) && move_buffer.has_played_tile() {
-            let score = move_buffer.calculate_score(&self.board, direction, cross_checks, anchor_pos);
-            let tiles_data: SmallVec<[PlayedTile; 7]> = move_buffer.data.iter().filter_map(|&tile| tile).collect();
-            let word_start_idx = move_buffer.data.iter().position(|tile| tile.is_some()).unwrap_or(0);
-            let word_start_pos = match direction {
-                Direction::Horizontal => Pos::new(anchor_pos.row, word_start_idx),
-                Direction::Vertical => Pos::new(word_start_idx, anchor_pos.col),
-            };
-            moves.push(Move {
-                tiles_data,
-                pos: word_start_pos,
-                direction,
-                score,
-            });
-        }
+        self.record_move(visitor, move_buffer, direction, anchor_pos, cross_checks, current_node)?;
 
-        if placed_tiles_seen && matches!(explore_dir, ExploreDir::Back) {
-            return;
-        }
+        // going back we stop at the next anchor, moves covering it are generated from there.
+        // this includes the square before any board tiles we walked through, it's always an anchor
+        let can_place = matches!(explore_dir, ExploreDir::Forward) || offset == 0 || !self.board.is_anchor(current_pos);
 
         let cross_check = unsafe { *cross_checks.get_unchecked(current_pos.row).get_unchecked(current_pos.col) };
         let cross_check_mask = cross_check.mask();
-        GADDAG.for_each_child(current_node, |letter| {
-            // if we hit the delimiter, we start looking right instead
+        let mut flow = ControlFlow::Continue(());
+        self.gaddag().for_each_child(current_node, |letter| {
+            // if we hit the delimiter, we start looking right instead.
+            // not before the anchor itself is covered, or the move belongs to another anchor
             if letter == super::gaddag::DELIMITER {
-                if let Some(delimiter_node) = GADDAG.can_next(current_node, letter) {
-                    self.explore(
-                        moves,
+                if offset == 0 {
+                    return true;
+                }
+                if let Some(delimiter_node) = self.gaddag().can_next(current_node, letter) {
+                    flow = self.explore(
+                        visitor,
                         move_buffer,
                         rack,
                         direction,
@@ -360,23 +641,26 @@ impl MoveGenerator {
                         suffix_offset as i8, // start at 1 to the right
                         ExploreDir::Forward,
                         delimiter_node,
-                        placed_tiles_seen,
                     );
                 }
-                return true;
+                return flow.is_continue();
             }
 
             // check the cross checks
-            if cross_check_mask & 1 << (letter - b'A') == 0 {
+            if !can_place || cross_check_mask & 1 << (letter - b'A') == 0 {
                 return true;
             }
 
-            // check rack
-            if let Some(tile) = rack.take_tile(letter) {
+            // check rack: the letter itself, then a blank standing for it. both are plays when the rack has both
+            for blank in [false, true] {
+                let taken = if blank { rack.take_blank(letter) } else { rack.take_letter(letter) };
+                let Some(tile) = taken else {
+                    continue;
+                };
                 move_buffer.set(board_idx, PlayedTile::Rack(tile));
-                if let Some(next_node) = GADDAG.can_next(current_node, letter) {
-                    self.explore(
-                        moves,
+                if let Some(next_node) = self.gaddag().can_next(current_node, letter) {
+                    flow = self.explore(
+                        visitor,
                         move_buffer,
                         rack,
                         direction,
@@ -386,13 +670,313 @@ impl MoveGenerator {
                         offset + explore_dir as i8,
                         explore_dir,
                         next_node,
-                        placed_tiles_seen,
                     );
                 }
                 move_buffer.unset(board_idx);
                 rack.add_tile(tile);
+                if flow.is_break() {
+                    break;
+                }
+            }
+            flow.is_continue()
+        });
+        flow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        testing::{boards, gaddag, place_word, rack},
+        words::other_direction,
+    };
+    use std::collections::BTreeMap;
+
+    // every play of `rack` by brute force: each word of the lexicon tried at every square of every line,
+    // kept if it uses a rack tile, touches the board and all its cross words are words.
+    // every way of covering the new letters with the rack's letters and blanks is a play of its own
+    fn brute_force(board: &Board, rack: &Rack, gaddag: &Gaddag) -> BTreeMap<MoveKey, u16> {
+        let alphabet = gaddag.alphabet();
+        let words: Vec<Vec<u8>> = gaddag.dawg().words().collect();
+        let centre = Pos::new(BOARD_SIZE / 2, BOARD_SIZE / 2);
+        let mut plays = BTreeMap::new();
+        for direction in [Direction::Horizontal, Direction::Vertical] {
+            let square = |line: usize, i: usize| match direction {
+                Direction::Horizontal => Pos::new(line, i),
+                Direction::Vertical => Pos::new(i, line),
+            };
+            for line in 0..BOARD_SIZE {
+                for start in 0..BOARD_SIZE {
+                    for word in words.iter().filter(|word| start + word.len() <= BOARD_SIZE) {
+                        let end = start + word.len();
+                        if (start > 0 && board.is_occupied(square(line, start - 1))) || (end < BOARD_SIZE && board.is_occupied(square(line, end))) {
+                            continue;
+                        }
+                        let mut letters = Vec::new();
+                        let fits = word.iter().enumerate().all(|(i, &letter)| {
+                            let pos = square(line, start + i);
+                            match board.get_board_tile(pos) {
+                                Some(tile) => tile.byte() == letter,
+                                None => {
+                                    letters.push((pos, letter));
+                                    true
+                                }
+                            }
+                        });
+                        if !fits || letters.is_empty() || letters.len() > rack.tiles().len() {
+                            continue;
+                        }
+
+                        let mut after = board.clone();
+                        for &(pos, letter) in &letters {
+                            after.place_tile(pos, Tile::letter(letter));
+                        }
+                        let mut formed = vec![after.word_at(letters[0].0, direction)];
+                        formed.extend(
+                            letters
+                                .iter()
+                                .map(|&(pos, _)| after.word_at(pos, other_direction(direction)))
+                                .filter(|word| word.len() > 1),
+                        );
+                        let connected = formed.iter().flatten().any(|&(pos, _)| board.is_occupied(pos))
+                            || (board.is_empty() && letters.iter().any(|&(pos, _)| pos == centre));
+                        let valid = formed
+                            .iter()
+                            .all(|word| gaddag.contains(&word.iter().map(|(_, tile)| tile.byte()).collect::<Vec<_>>()));
+                        if !connected || !valid {
+                            continue;
+                        }
+
+                        // bit i set: the i-th new letter is a blank
+                        for blanks in 0..1u32 << letters.len() {
+                            let mut left = rack.clone();
+                            let placed: Vec<(Pos, Tile)> = letters
+                                .iter()
+                                .enumerate()
+                                .map(|(i, &(pos, letter))| {
+                                    (
+                                        pos,
+                                        if blanks & 1 << i != 0 {
+                                            Tile::blank(Some(letter))
+                                        } else {
+                                            Tile::letter(letter)
+                                        },
+                                    )
+                                })
+                                .collect();
+                            if !placed.iter().all(|&(_, tile)| left.remove_tile(tile)) {
+                                continue;
+                            }
+                            let tile_at = |pos: Pos, tile: Tile| placed.iter().find(|&&(at, _)| at == pos).map_or(tile, |&(_, placed)| placed);
+
+                            let mut score: u16 = formed
+                                .iter()
+                                .map(|word| {
+                                    let mut word_multiplier = 1;
+                                    let mut sum = 0;
+                                    for &(pos, tile) in word {
+                                        let points = alphabet.points(tile_at(pos, tile)) as u16;
+                                        let multiplier = if board.is_occupied(pos) { None } else { board.get_multiplier(pos) };
+                                        sum += match multiplier {
+                                            Some(Multiplier::DoubleLetter) => 2 * points,
+                                            Some(Multiplier::TripleLetter) => 3 * points,
+                                            Some(Multiplier::DoubleWord) => {
+                                                word_multiplier *= 2;
+                                                points
+                                            }
+                                            Some(Multiplier::TripleWord) => {
+                                                word_multiplier *= 3;
+                                                points
+                                            }
+                                            None => points,
+                                        };
+                                    }
+                                    sum * word_multiplier
+                                })
+                                .sum();
+                            if placed.len() == RACK_TILES {
+                                score += 50;
+                            }
+                            let mut placed: SmallVec<[(Pos, Tile); 7]> = placed.into_iter().collect();
+                            placed.sort_unstable();
+                            plays.insert(MoveKey(placed), score);
+                        }
+                    }
+                }
+            }
+        }
+        plays
+    }
+
+    fn generated(board: &Board, rack: &Rack, gaddag: &Arc<Gaddag>) -> BTreeMap<MoveKey, u16> {
+        let moves = MoveGenerator::run(board.clone(), rack.clone(), gaddag.clone());
+        let plays: BTreeMap<MoveKey, u16> = moves.iter().map(|mv| (mv.key(), mv.score)).collect();
+        assert_eq!(plays.len(), moves.len(), "a play was generated twice");
+        plays
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let gaddag = gaddag();
+        for board in boards() {
+            for letters in ["AENORST", "CATSEOR", "NOTE", "S", "AENRST?", "NOTE?", "S?", "??"] {
+                let rack = rack(letters);
+                assert_eq!(generated(&board, &rack, &gaddag), brute_force(&board, &rack, &gaddag), "{}", letters);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_hooks_and_parallel_plays() {
+        let gaddag = gaddag();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let plays = generated(&board, &rack("AST"), &gaddag);
+        let key = |tiles: &[(usize, usize, u8)]| {
+            let mut placed: SmallVec<[(Pos, Tile); 7]> = tiles
+                .iter()
+                .map(|&(row, col, letter)| (Pos::new(row, col), Tile::letter(letter)))
+                .collect();
+            placed.sort_unstable();
+            MoveKey(placed)
+        };
+
+        // CATS and SCAT
+        assert!(plays.contains_key(&key(&[(7, 10, b'S')])));
+        assert!(plays.contains_key(&key(&[(7, 6, b'S')])));
+        // TA over the AT of CAT, making TA and AT down
+        assert!(plays.contains_key(&key(&[(6, 8, b'T'), (6, 9, b'A')])));
+        // AS down from the A, a single tile found from both directions but generated once
+        assert_eq!(plays.get(&key(&[(8, 8, b'S')])), Some(&3));
+    }
+
+    #[test]
+    fn moves_compare_by_placed_tiles() {
+        let s = Tile::letter(b'S');
+        // AS down through the A of CAT, or the S on its own across
+        let down = Move {
+            tiles_data: SmallVec::from_slice(&[PlayedTile::Board(Tile::letter(b'A')), PlayedTile::Rack(s)]),
+            pos: Pos::new(7, 8),
+            direction: Direction::Vertical,
+            score: 3,
+        };
+        let across = Move {
+            tiles_data: SmallVec::from_slice(&[PlayedTile::Rack(s)]),
+            pos: Pos::new(8, 8),
+            direction: Direction::Horizontal,
+            score: 3,
+        };
+        assert_eq!(down, across);
+        assert_eq!(down.cmp(&across), Ordering::Equal);
+        assert_eq!(std::collections::HashSet::from([down.clone(), across]).len(), 1);
+
+        // a blank S is a different play
+        let blank = Move {
+            tiles_data: SmallVec::from_slice(&[PlayedTile::Board(Tile::letter(b'A')), PlayedTile::Rack(Tile::blank(Some(b'S')))]),
+            ..down.clone()
+        };
+        assert_ne!(down, blank);
+    }
+
+    #[test]
+    fn no_duplicate_moves() {
+        let gaddag = gaddag();
+        for board in boards() {
+            for letters in ["AENORST", "S?", "TT"] {
+                let moves = MoveGenerator::run(board.clone(), rack(letters), gaddag.clone());
+                let mut sorted = moves.clone();
+                sorted.sort();
+                sorted.dedup();
+                assert_eq!(sorted.len(), moves.len(), "{}", letters);
+            }
+        }
+    }
+
+    #[test]
+    fn cached_anchors_match_rebuilt() {
+        let gaddag = gaddag();
+        let mut cached = AnchoredBoard::new(Board::new(), gaddag.clone());
+        for board in boards().into_iter().skip(1) {
+            let new_tiles: Vec<(Pos, Tile)> = board.tiles().filter(|&(pos, _)| !cached.is_occupied(pos)).collect();
+            cached.place_tiles(new_tiles);
+            let rebuilt = AnchoredBoard::new(board, gaddag.clone());
+
+            assert_eq!(cached.anchors().collect::<Vec<_>>(), rebuilt.anchors().collect::<Vec<_>>());
+            for direction in [Direction::Horizontal, Direction::Vertical] {
+                let checks = |board: &AnchoredBoard| -> Vec<(bool, u64, u16)> {
+                    board
+                        .cross_checks(direction)
+                        .iter()
+                        .flatten()
+                        .map(|check| (check.has_word(), check.mask(), check.score()))
+                        .collect()
+                };
+                assert_eq!(checks(&cached), checks(&rebuilt));
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let gaddag = gaddag();
+        for board in boards() {
+            for letters in ["AENORST", "AENRST?", "S"] {
+                let generator = MoveGenerator {
+                    board: AnchoredBoard::new(board.clone(), gaddag.clone()),
+                    rack: rack(letters),
+                };
+                let serial: Vec<MoveKey> = generator.generate_moves().iter().map(Move::key).collect();
+                for threads in [1, 2, 8] {
+                    let parallel: Vec<MoveKey> = generator.generate_moves_parallel(threads).iter().map(Move::key).collect();
+                    assert_eq!(parallel, serial, "{} on {} threads", letters, threads);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn visitors_agree_with_generate_moves() {
+        let gaddag = gaddag();
+        for board in boards() {
+            for letters in ["AENORST", "AENRST?", "CATS", "ZZ"] {
+                let generator = MoveGenerator {
+                    board: AnchoredBoard::new(board.clone(), gaddag.clone()),
+                    rack: rack(letters),
+                };
+                let moves = generator.generate_moves();
+                assert_eq!(generator.count_moves(), moves.len());
+                assert_eq!(generator.has_bingo(), moves.iter().any(|mv| mv.tiles_placed() == RACK_TILES));
+
+                // best first, generation order between equal scores
+                let mut by_score = moves.clone();
+                by_score.sort_by_key(|mv| Reverse(mv.score));
+                for k in [0, 1, 5, moves.len() + 1] {
+                    let top: Vec<MoveKey> = generator.top_moves(k).iter().map(Move::key).collect();
+                    let expected: Vec<MoveKey> = by_score.iter().take(k).map(Move::key).collect();
+                    assert_eq!(top, expected, "{} top {}", letters, k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn visitor_break_stops_early() {
+        let generator = MoveGenerator {
+            board: AnchoredBoard::new(Board::new(), gaddag()),
+            rack: rack("AENORST"),
+        };
+        let mut visited = 0;
+        let flow = generator.visit_moves(&mut |_: &CandidateMove| {
+            visited += 1;
+            if visited == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
             }
-            true
         });
+        assert!(flow.is_break());
+        assert_eq!(visited, 3);
+        assert!(generator.count_moves() > 3);
     }
 }
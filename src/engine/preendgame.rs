@@ -0,0 +1,402 @@
+use super::{
+    actions::Action,
+    endgame::{EndgameSolver, gain},
+    evaluator::Evaluator,
+    inference::RackInference,
+    moves::MoveGenerator,
+};
+use crate::game::{Game, alphabet::MAX_TILE_TYPES, rack::Rack, tile::Tile};
+use std::time::{Duration, Instant};
+
+// pre-endgame: with a tile or two left in the bag, the unseen tiles are the opponent's rack plus the bag.
+// every order the bag can be drawn in is tried against every candidate: the candidate draws its tiles off the front,
+// the opponent holds the rest of the unseen tiles, and the endgame solver plays it out from there.
+// draw orders are weighted by how many ways the tiles can come out, so duplicates count for each copy.
+// the solver only ever gets an empty bag: while tiles are left, whoever is to move picks from their best
+// scoring plays and a pass, drawing the next tiles of the order, until the bag runs out or the game ends.
+// two passes in a row end it there like in the solver, and spreads are counted from the racks like the solver does
+
+#[derive(Debug, Clone)]
+pub struct PreEndgameConfig {
+    pub candidates: usize,    // how many of the evaluator's best plays get analyzed, and replies tried while the bag has tiles
+    pub endgame_depth: usize, // plies each endgame is searched to
+    pub endgame_time_limit: Option<Duration>, // per endgame
+    pub time_limit: Option<Duration>, // for the whole analysis, candidates not finished by then are left out
+    pub threads: usize,
+    pub inference: Option<RackInference>, // draws are weighted by how likely the opponent's rack is under this
+}
+
+impl Default for PreEndgameConfig {
+    fn default() -> Self {
+        PreEndgameConfig {
+            candidates: 10,
+            endgame_depth: 8,
+            endgame_time_limit: Some(Duration::from_millis(500)),
+            time_limit: Some(Duration::from_secs(30)),
+            threads: 1,
+            inference: None,
+        }
+    }
+}
+
+// one way the bag can be drawn, front first, and how an analyzed play does with it
+#[derive(Debug, Clone)]
+pub struct DrawOutcome {
+    pub draw: Vec<Tile>,
+    pub weight: f64,
+    pub spread: i32, // final spread for the player making the play
+}
+
+#[derive(Debug, Clone)]
+pub struct PreEndgameCandidate {
+    pub action: Action,
+    pub outcomes: Vec<DrawOutcome>,
+    pub wins: f64, // weighted, ties count half
+    pub total_weight: f64,
+    pub expected_spread: f64,
+}
+
+impl PreEndgameCandidate {
+    pub fn win_rate(&self) -> f64 {
+        if self.total_weight == 0.0 { 0.0 } else { self.wins / self.total_weight }
+    }
+
+    // the draws the play doesn't win
+    pub fn losing_draws(&self) -> impl Iterator<Item = &DrawOutcome> {
+        self.outcomes.iter().filter(|outcome| outcome.spread < 0)
+    }
+}
+
+// tiles the player to move can't see, counted by Tile::to_index
+pub fn unseen_tiles(game: &Game) -> [u8; MAX_TILE_TYPES] {
+    let mut unseen = game.bag.tiles;
+    for tile in game.racks[1 - game.current_player].tiles() {
+        unseen[tile.to_index() as usize] += 1;
+    }
+    unseen
+}
+
+/// Every distinct order `count` tiles can be drawn from `unseen` in, with how many ways each can happen.
+pub fn draw_orders(unseen: &[u8; MAX_TILE_TYPES], count: usize) -> Vec<(Vec<Tile>, u32)> {
+    fn extend(unseen: &mut [u8; MAX_TILE_TYPES], count: usize, draw: &mut Vec<Tile>, weight: u32, out: &mut Vec<(Vec<Tile>, u32)>) {
+        if draw.len() == count {
+            out.push((draw.clone(), weight));
+            return;
+        }
+        for index in 0..MAX_TILE_TYPES {
+            let available = unseen[index];
+            if available == 0 {
+                continue;
+            }
+            unseen[index] -= 1;
+            draw.push(Tile::from_index(index as u8));
+            extend(unseen, count, draw, weight * available as u32, out);
+            draw.pop();
+            unseen[index] += 1;
+        }
+    }
+
+    let mut out = Vec::new();
+    extend(&mut unseen.clone(), count, &mut Vec::new(), 1, &mut out);
+    out
+}
+
+// the unseen tiles that aren't in `draw`, which is the opponent's rack with that draw
+fn opponent_rack(game: &Game, draw: &[Tile]) -> Vec<Tile> {
+    let mut unseen = unseen_tiles(game);
+    for tile in draw {
+        unseen[tile.to_index() as usize] -= 1;
+    }
+    unseen
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &count)| std::iter::repeat_n(Tile::from_index(index as u8), count as usize))
+        .collect()
+}
+
+// every draw order with its weight: the ways it can happen times how likely the opponent's rack is.
+// when the inference rules out every rack it's wrong about this position, so the orders go back to their ways
+fn weighted_orders(game: &Game, config: &PreEndgameConfig) -> Vec<(Vec<Tile>, f64)> {
+    let orders = draw_orders(&unseen_tiles(game), game.bag.len());
+    let Some(inference) = &config.inference else {
+        return orders.into_iter().map(|(draw, ways)| (draw, ways as f64)).collect();
+    };
+    let weighted: Vec<(Vec<Tile>, f64)> = orders
+        .iter()
+        .map(|(draw, ways)| (draw.clone(), *ways as f64 * inference.likelihood(&opponent_rack(game, draw))))
+        .collect();
+    if weighted.iter().any(|(_, weight)| *weight > 0.0) {
+        weighted
+    } else {
+        orders.into_iter().map(|(draw, ways)| (draw, ways as f64)).collect()
+    }
+}
+
+// plays `action` with the bag holding `draw`, front first. the bag is set to just the tiles the action draws so
+// nothing is left to chance, the rest go back in after. gives the position and what's still to be drawn
+fn apply<'a>(game: &Game, action: &Action, draw: &'a [Tile]) -> (Game, &'a [Tile]) {
+    let drawn = match action {
+        Action::Move(mv) => mv.tiles_placed().min(draw.len()),
+        _ => 0,
+    };
+    let mut game = game.clone();
+    game.bag.tiles = [0; MAX_TILE_TYPES];
+    for tile in &draw[..drawn] {
+        game.bag.tiles[tile.to_index() as usize] += 1;
+    }
+    game.play_action(action);
+    for tile in &draw[drawn..] {
+        game.bag.tiles[tile.to_index() as usize] += 1;
+    }
+    (game, &draw[drawn..])
+}
+
+// the best scoring plays and a pass, what the side to move picks from while the bag still has tiles
+fn replies(game: &Game, config: &PreEndgameConfig) -> Vec<Action> {
+    let generator = MoveGenerator {
+        board: game.board.clone(),
+        rack: game.racks[game.current_player].clone(),
+    };
+    let mut moves = generator.generate_moves();
+    moves.sort_by_key(|mv| std::cmp::Reverse(mv.score));
+    let mut actions: Vec<Action> = moves.into_iter().take(config.candidates).map(Action::Move).collect();
+    actions.push(Action::Pass);
+    actions
+}
+
+// what the side to move gains from here with `draw` left in the bag, front first. `passed` if the last turn was a pass.
+// None once the deadline has passed
+fn play_out(
+    game: &Game,
+    passed: bool,
+    draw: &[Tile],
+    config: &PreEndgameConfig,
+    deadline: Option<Instant>,
+    solver: &mut EndgameSolver,
+) -> Option<i32> {
+    if game.is_over() {
+        return Some(0);
+    }
+    if draw.is_empty() && !passed {
+        let time_limit = match deadline {
+            Some(deadline) => {
+                let left = deadline.checked_duration_since(Instant::now()).filter(|left| !left.is_zero())?;
+                Some(config.endgame_time_limit.map_or(left, |limit| limit.min(left)))
+            }
+            None => config.endgame_time_limit,
+        };
+        return solver.solve(game, config.endgame_depth, time_limit).map(|result| result.value);
+    }
+
+    let mut best = None;
+    for action in replies(game, config) {
+        // a second pass in a row ends the game with both racks counted
+        let ends = passed && matches!(action, Action::Pass);
+        let value = if ends {
+            gain(game, &action, game, true)
+        } else {
+            let (child, rest) = apply(game, &action, draw);
+            let passing = matches!(action, Action::Pass);
+            // the opponent is to move, their gain is our loss
+            gain(game, &action, &child, false) - play_out(&child, passing, rest, config, deadline, solver)?
+        };
+        best = best.max(Some(value));
+    }
+    best
+}
+
+// plays `action` with the bag drawn in `draw` order and the opponent on the other unseen tiles.
+// gives the final spread for the player making the play, None once the deadline has passed
+fn play_candidate(
+    game: &Game,
+    action: &Action,
+    draw: &[Tile],
+    config: &PreEndgameConfig,
+    deadline: Option<Instant>,
+    solver: &mut EndgameSolver,
+) -> Option<i32> {
+    let player = game.current_player;
+    let mut game = game.clone();
+    game.racks[1 - player] = Rack::new(opponent_rack(&game, draw));
+    let (child, rest) = apply(&game, action, draw);
+    let spread = game.scores[player] as i32 - game.scores[1 - player] as i32;
+    let passing = matches!(action, Action::Pass);
+    Some(spread + gain(&game, action, &child, false) - play_out(&child, passing, rest, config, deadline, solver)?)
+}
+
+/// Analyzes `actions` for the player to move against every draw of the bag. Exact once the bag is empty,
+/// while it isn't only the best `config.candidates` scoring replies and a pass are tried, so keep the bag to a tile or two.
+/// Exchanges are left out, the bag is too small for them. So is every candidate not finished within `config.time_limit`,
+/// they're analyzed in the order given.
+pub fn analyze(game: &Game, actions: Vec<Action>, config: &PreEndgameConfig) -> Vec<PreEndgameCandidate> {
+    let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+    let orders = weighted_orders(game, config);
+    let threads = config.threads.clamp(1, orders.len().max(1));
+
+    let mut candidates: Vec<PreEndgameCandidate> = actions
+        .into_iter()
+        .filter(|action| !matches!(action, Action::Swap(_)))
+        .map_while(|action| {
+            // the draws split over the threads, each with its own solver
+            let outcomes: Vec<DrawOutcome> = std::thread::scope(|scope| {
+                let handles: Vec<_> = orders
+                    .chunks(orders.len().div_ceil(threads).max(1))
+                    .map(|chunk| {
+                        let action = &action;
+                        scope.spawn(move || {
+                            let mut solver = EndgameSolver::new();
+                            chunk
+                                .iter()
+                                .map(|(draw, weight)| {
+                                    Some(DrawOutcome {
+                                        spread: play_candidate(game, action, draw, config, deadline, &mut solver)?,
+                                        weight: *weight,
+                                        draw: draw.clone(),
+                                    })
+                                })
+                                .collect::<Option<Vec<_>>>()
+                        })
+                    })
+                    .collect();
+                let chunks: Option<Vec<Vec<DrawOutcome>>> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+                chunks.map(|chunks| chunks.into_iter().flatten().collect())
+            })?;
+
+            let total_weight: f64 = outcomes.iter().map(|outcome| outcome.weight).sum();
+            let wins: f64 = outcomes
+                .iter()
+                .map(|outcome| match outcome.spread.signum() {
+                    1 => outcome.weight,
+                    0 => outcome.weight / 2.0,
+                    _ => 0.0,
+                })
+                .sum();
+            let spread_sum: f64 = outcomes.iter().map(|outcome| outcome.spread as f64 * outcome.weight).sum();
+            Some(PreEndgameCandidate {
+                action,
+                wins,
+                total_weight,
+                expected_spread: if total_weight == 0.0 { 0.0 } else { spread_sum / total_weight },
+                outcomes,
+            })
+        })
+        .collect();
+
+    // most wins, then best spread
+    candidates.sort_by(|a, b| {
+        b.win_rate()
+            .total_cmp(&a.win_rate())
+            .then_with(|| b.expected_spread.total_cmp(&a.expected_spread))
+    });
+    candidates
+}
+
+/// Analyzes the evaluator's `config.candidates` best plays and a pass for the player to move.
+pub fn analyze_top(game: &Game, evaluator: &Evaluator, config: &PreEndgameConfig) -> Vec<PreEndgameCandidate> {
+    let generator = MoveGenerator {
+        board: game.board.clone(),
+        rack: game.racks[game.current_player].clone(),
+    };
+    let mut actions: Vec<Action> = evaluator
+        .rank(&generator, game.bag.len())
+        .into_iter()
+        .take(config.candidates)
+        .map(|ranked| Action::Move(ranked.mv))
+        .collect();
+    actions.push(Action::Pass);
+    analyze(game, actions, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Direction, Pos,
+        engine::{
+            anchors::AnchoredBoard,
+            inference::InferredLeave,
+            testing::{gaddag, place_word, rack},
+        },
+        game::board::Board,
+    };
+
+    // CAT across the centre, SZ to play, Q and E unseen with one of them in the bag
+    fn position() -> Game {
+        let gaddag = gaddag();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let mut game = Game::init(gaddag.clone());
+        game.board = AnchoredBoard::new(board, gaddag);
+        game.racks = [rack("SZ"), rack("Q")];
+        game.bag.tiles = [0; MAX_TILE_TYPES];
+        game.bag.tiles[Tile::letter(b'E').to_index() as usize] = 1;
+        game
+    }
+
+    // each candidate's spread when the bag holds E, then when it holds Q
+    fn spreads(candidate: &PreEndgameCandidate) -> Vec<(Tile, i32)> {
+        candidate.outcomes.iter().map(|outcome| (outcome.draw[0], outcome.spread)).collect()
+    }
+
+    #[test]
+    fn plays_out_every_draw() {
+        let e = Tile::letter(b'E');
+        let q = Tile::letter(b'Q');
+        let candidates = analyze_top(&position(), &Evaluator::default(), &PreEndgameConfig::default());
+        assert_eq!(candidates.len(), 4);
+        for candidate in &candidates {
+            assert_eq!(candidate.total_weight, 2.0);
+        }
+
+        // CATS or SCAT for 6 go first. drawing E, the Q is stuck and AE gets 3 more before the Z and Q cancel out.
+        // drawing Q, the opponent goes out with AE for 3 and twice the Z and Q
+        let best = &candidates[0];
+        assert!(matches!(&best.action, Action::Move(mv) if mv.score == 6));
+        assert_eq!(spreads(best), vec![(e, 9), (q, 6 - 3 - 40)]);
+        assert_eq!(best.win_rate(), 0.5);
+        assert_eq!(best.losing_draws().map(|outcome| outcome.draw.clone()).collect::<Vec<_>>(), vec![vec![q]]);
+
+        // AS for 3 blocks AE, so the E only makes ET for 2 either way
+        let as_down = candidates
+            .iter()
+            .find(|candidate| matches!(&candidate.action, Action::Move(mv) if mv.score == 3))
+            .unwrap();
+        assert_eq!(spreads(as_down), vec![(e, 3 + 2), (q, 3 - 2 - 40)]);
+
+        // passing leaves the tile in the bag. the Q can only pass back, ending it 1 down on the racks,
+        // and holding the E the opponent would rather pass too than draw the Q
+        let pass = candidates.last().unwrap();
+        assert!(matches!(pass.action, Action::Pass));
+        assert_eq!(spreads(pass), vec![(e, 10 - 11), (q, 1 - 11)]);
+        assert_eq!(pass.win_rate(), 0.0);
+    }
+
+    #[test]
+    fn leaves_out_swaps_and_whatever_runs_out_of_time() {
+        let config = PreEndgameConfig {
+            time_limit: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert!(analyze(&position(), vec![Action::Pass], &config).is_empty());
+        let swap = Action::Swap(rack("Z").tiles().to_vec());
+        assert!(analyze(&position(), vec![swap], &PreEndgameConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn ignores_inference_that_rules_out_every_rack() {
+        // the opponent holds one of E and Q, never a Z
+        let inference = RackInference {
+            leaves: vec![InferredLeave {
+                tiles: rack("Z").tiles().to_vec(),
+                weight: 1.0,
+            }],
+        };
+        let config = PreEndgameConfig {
+            inference: Some(inference),
+            ..Default::default()
+        };
+        let orders = weighted_orders(&position(), &config);
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|(_, weight)| *weight == 1.0));
+    }
+}
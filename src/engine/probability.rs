@@ -0,0 +1,148 @@
+use super::gaddag::Gaddag;
+use crate::game::{
+    alphabet::{MAX_LETTERS, MAX_TILE_TYPES},
+    bag::Bag,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::RangeInclusive,
+};
+
+// draw odds of words from a bag, like zyzzyva's probability order.
+// the number of ways to draw a word is counted over which of its letters are covered by blanks:
+// prod over letters of (sum over r of C(count, needed - r) x^r), and x^b gets C(blanks, b)
+pub struct DrawOdds {
+    counts: [u8; MAX_TILE_TYPES], // indexed by Tile::to_index, blanks at 0
+    total: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StudyEntry {
+    pub rank: usize, // 1 is the most likely alphagram of its length
+    pub alphagram: String,
+    pub words: Vec<String>,
+    pub combinations: u64,
+    pub probability: f64,
+}
+
+fn choose(n: usize, k: usize) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    let mut result = 1u128;
+    for i in 0..k {
+        result = result * (n - i) as u128 / (i + 1) as u128;
+    }
+    result as u64
+}
+
+impl DrawOdds {
+    pub fn new(bag: &Bag) -> Self {
+        DrawOdds {
+            counts: bag.tiles,
+            total: bag.tiles.iter().map(|&count| count as usize).sum(),
+        }
+    }
+
+    /// Number of distinct tile draws (of `word.len()` tiles) that spell the word, blanks included.
+    pub fn combinations(&self, word: &[u8]) -> u64 {
+        let mut needed = [0usize; MAX_LETTERS];
+        for &letter in word {
+            needed[(letter - b'A') as usize] += 1;
+        }
+
+        // poly[b] = ways to draw the real tiles when b letters are left for blanks
+        let mut poly = vec![1u64];
+        for (i, &need) in needed.iter().enumerate().filter(|(_, need)| **need > 0) {
+            let count = self.counts[i + 1] as usize;
+            let mut next = vec![0u64; poly.len() + need];
+            for (b, &ways) in poly.iter().enumerate() {
+                for r in 0..=need {
+                    next[b + r] += ways * choose(count, need - r);
+                }
+            }
+            poly = next;
+        }
+
+        let blanks = self.counts[0] as usize;
+        poly.iter().enumerate().map(|(b, &ways)| ways * choose(blanks, b)).sum()
+    }
+
+    pub fn probability(&self, word: &[u8]) -> f64 {
+        let draws = choose(self.total, word.len());
+        if draws == 0 {
+            0.0
+        } else {
+            self.combinations(word) as f64 / draws as f64
+        }
+    }
+}
+
+/// Probability ordered alphagram lists for every length in `lengths`, most likely first.
+/// Alphagrams with the same odds are ordered alphabetically, so the ranks are stable.
+pub fn study_lists(gaddag: &Gaddag, odds: &DrawOdds, lengths: RangeInclusive<usize>) -> BTreeMap<usize, Vec<StudyEntry>> {
+    let alphabet = gaddag.alphabet();
+    let mut alphagrams: HashMap<Vec<u8>, Vec<String>> = HashMap::new();
+    for word in gaddag.dawg().words().filter(|word| lengths.contains(&word.len())) {
+        let mut alphagram = word.clone();
+        alphagram.sort_unstable();
+        alphagrams.entry(alphagram).or_default().push(alphabet.decode(&word));
+    }
+
+    // (combinations, alphagram, words) by length
+    type Entry = (u64, Vec<u8>, Vec<String>);
+    let mut lists: BTreeMap<usize, Vec<Entry>> = BTreeMap::new();
+    for (alphagram, words) in alphagrams {
+        lists
+            .entry(alphagram.len())
+            .or_default()
+            .push((odds.combinations(&alphagram), alphagram, words));
+    }
+
+    lists
+        .into_iter()
+        .map(|(len, mut entries)| {
+            entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+            let draws = choose(odds.total, len) as f64;
+            let entries = entries
+                .into_iter()
+                .enumerate()
+                .map(|(i, (combinations, alphagram, words))| StudyEntry {
+                    rank: i + 1,
+                    alphagram: alphabet.decode(&alphagram),
+                    words, // already sorted, the dawg streams in order
+                    combinations,
+                    probability: combinations as f64 / draws,
+                })
+                .collect();
+            (len, entries)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::alphabet::Alphabet;
+
+    #[test]
+    fn blanks_stand_in_for_missing_letters() {
+        let odds = DrawOdds::new(&Bag::new(&Alphabet::english()));
+        // QI: Q and I, a blank for the Q (9 I's x 2 blanks), a blank for the I (1 Q x 2 blanks), or both blanks
+        assert_eq!(odds.combinations(b"QI"), 9 + 18 + 2 + 1);
+        // there's one Z, so ZZ needs a blank for one Z or both
+        assert_eq!(odds.combinations(b"ZZ"), 2 + 1);
+        assert_eq!(odds.probability(b"QI"), 30.0 / choose(100, 2) as f64);
+    }
+
+    #[test]
+    fn no_blanks_is_plain_counting() {
+        let mut bag = Bag::new(&Alphabet::english());
+        bag.tiles[0] = 0;
+        let odds = DrawOdds::new(&bag);
+        // 9 A's, 2 B's
+        assert_eq!(odds.combinations(b"AAB"), choose(9, 2) * 2);
+        assert_eq!(odds.combinations(b"ZZ"), 0);
+    }
+}
@@ -0,0 +1,248 @@
+use super::gaddag::{DELIMITER, Gaddag};
+use crate::game::alphabet::{Alphabet, MAX_LETTERS};
+use fst::raw::CompiledAddr;
+use std::{collections::BTreeSet, ops::RangeInclusive};
+
+// word finder on top of the gaddag. every full word is stored reversed without a delimiter,
+// so walking the root without ever taking the DELIMITER edge visits each word exactly once
+
+#[derive(Debug, Clone, Copy)]
+enum PatternToken {
+    Letters(u64), // bitmask of allowed letters, so plain letters, ? and [..] all end up here
+    Star,
+}
+
+// counts of each letter on the rack, blanks separately
+struct RackCounts {
+    letters: [u8; MAX_LETTERS],
+    blanks: u8,
+    total: usize,
+}
+
+impl RackCounts {
+    fn parse(rack: &str, alphabet: &Alphabet) -> Result<Self, Box<dyn std::error::Error>> {
+        let tiles = alphabet
+            .parse_tiles(&rack.to_uppercase())
+            .ok_or_else(|| format!("invalid rack {}", rack))?;
+        let mut counts = RackCounts {
+            letters: [0; MAX_LETTERS],
+            blanks: 0,
+            total: tiles.len(),
+        };
+        for tile in tiles {
+            if tile.is_blank() {
+                counts.blanks += 1;
+            } else {
+                counts.letters[(tile.byte() - b'A') as usize] += 1;
+            }
+        }
+        Ok(counts)
+    }
+}
+
+fn parse_pattern(pattern: &str, alphabet: &Alphabet) -> Result<Vec<PatternToken>, Box<dyn std::error::Error>> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let invalid = |i: usize| format!("invalid character {:?} in pattern {}", chars[i], pattern);
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '?' | '.' => tokens.push(PatternToken::Letters(alphabet.all_letters_mask())),
+            '*' => {
+                // ** is the same as *, and only makes duplicate work
+                if !matches!(tokens.last(), Some(PatternToken::Star)) {
+                    tokens.push(PatternToken::Star);
+                }
+            }
+            '[' => {
+                let end = match chars[i..].iter().position(|&c| c == ']') {
+                    Some(end) => i + end,
+                    None => return Err(format!("unclosed [ in pattern {}", pattern).into()),
+                };
+                let mut start = i + 1;
+                let negated = chars.get(start) == Some(&'^');
+                if negated {
+                    start += 1;
+                }
+
+                let mut mask = 0u64;
+                while start < end {
+                    let (letter, len, _) = alphabet.match_letter(&chars[..end], start).ok_or_else(|| invalid(start))?;
+                    mask |= 1 << (letter - b'A');
+                    start += len;
+                }
+                tokens.push(PatternToken::Letters(if negated { !mask & alphabet.all_letters_mask() } else { mask }));
+                i = end;
+            }
+            _ => {
+                let (letter, len, _) = alphabet.match_letter(&chars, i).ok_or_else(|| invalid(i))?;
+                tokens.push(PatternToken::Letters(1 << (letter - b'A')));
+                i += len;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+impl Gaddag {
+    /// Words that use every tile of the rack. `?` is a blank.
+    pub fn anagrams(&self, rack: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut counts = RackCounts::parse(rack, self.alphabet())?;
+        let length = counts.total;
+        let mut words = Vec::new();
+        self.find_anagrams(self.root(), &mut counts, &mut Vec::new(), length..=length, &mut words);
+        words.sort();
+        Ok(words)
+    }
+
+    /// Words that can be built from some of the tiles of the rack ("build" mode), longest first.
+    pub fn sub_anagrams(&self, rack: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut counts = RackCounts::parse(rack, self.alphabet())?;
+        let length = counts.total;
+        let mut words = Vec::new();
+        self.find_anagrams(self.root(), &mut counts, &mut Vec::new(), 2..=length, &mut words);
+        words.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Ok(words)
+    }
+
+    /// Words matching a pattern like `?A??E*` or `Q[^U]*`, restricted to the given lengths.
+    /// `?` (or `.`) is any letter, `*` is any run of letters, `[ABC]` and `[^ABC]` are letter classes.
+    pub fn pattern_matches(&self, pattern: &str, lengths: RangeInclusive<usize>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let mut tokens = parse_pattern(pattern, self.alphabet())?;
+        tokens.reverse(); // words are stored back to front
+
+        let mut words = BTreeSet::new();
+        self.find_pattern(self.root(), &tokens, 0, &mut Vec::new(), &lengths, &mut words);
+        Ok(words.into_iter().collect())
+    }
+
+    fn find_anagrams(
+        &self,
+        node_addr: CompiledAddr,
+        counts: &mut RackCounts,
+        path: &mut Vec<u8>,
+        lengths: RangeInclusive<usize>,
+        words: &mut Vec<String>,
+    ) {
+        if self.is_terminal(node_addr) && lengths.contains(&path.len()) {
+            words.push(path.iter().rev().map(|&b| self.alphabet().byte_label(b)).collect());
+        }
+        if path.len() >= *lengths.end() {
+            return;
+        }
+
+        for transition in self.node_at(node_addr).transitions() {
+            if transition.inp == DELIMITER {
+                continue;
+            }
+
+            // a real tile is never worse than the blank, and trying both would find the same word twice
+            let idx = (transition.inp - b'A') as usize;
+            let used_blank = if counts.letters[idx] > 0 {
+                counts.letters[idx] -= 1;
+                false
+            } else if counts.blanks > 0 {
+                counts.blanks -= 1;
+                true
+            } else {
+                continue;
+            };
+
+            path.push(transition.inp);
+            self.find_anagrams(transition.addr, counts, path, lengths.clone(), words);
+            path.pop();
+
+            if used_blank {
+                counts.blanks += 1;
+            } else {
+                counts.letters[idx] += 1;
+            }
+        }
+    }
+
+    fn find_pattern(
+        &self,
+        node_addr: CompiledAddr,
+        tokens: &[PatternToken],
+        token_idx: usize,
+        path: &mut Vec<u8>,
+        lengths: &RangeInclusive<usize>,
+        words: &mut BTreeSet<String>,
+    ) {
+        let Some(&token) = tokens.get(token_idx) else {
+            if self.is_terminal(node_addr) && lengths.contains(&path.len()) {
+                words.insert(path.iter().rev().map(|&b| self.alphabet().byte_label(b)).collect());
+            }
+            return;
+        };
+
+        // star can also match nothing
+        if matches!(token, PatternToken::Star) {
+            self.find_pattern(node_addr, tokens, token_idx + 1, path, lengths, words);
+        }
+        if path.len() >= *lengths.end() {
+            return;
+        }
+
+        for transition in self.node_at(node_addr).transitions() {
+            if transition.inp == DELIMITER {
+                continue;
+            }
+
+            let next_idx = match token {
+                PatternToken::Letters(mask) if mask & 1 << (transition.inp - b'A') != 0 => token_idx + 1,
+                PatternToken::Letters(_) => continue,
+                PatternToken::Star => token_idx, // keep matching the star
+            };
+
+            path.push(transition.inp);
+            self.find_pattern(transition.addr, tokens, next_idx, path, lengths, words);
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::testing::gaddag;
+
+    #[test]
+    fn anagrams_use_the_whole_rack() {
+        let gaddag = gaddag();
+        assert_eq!(gaddag.anagrams("TAC").unwrap(), ["ACT", "CAT"]);
+        // the blank fills in for anything, and lowercase racks are fine
+        assert_eq!(gaddag.anagrams("ca?").unwrap(), ["ACT", "CAR", "CAT"]);
+        assert_eq!(gaddag.anagrams("??").unwrap().len(), 30);
+        assert!(gaddag.anagrams("QQ").unwrap().is_empty());
+        assert!(gaddag.anagrams("A1").is_err());
+    }
+
+    #[test]
+    fn sub_anagrams_longest_first() {
+        let gaddag = gaddag();
+        assert_eq!(
+            gaddag.sub_anagrams("TAE").unwrap(),
+            ["ATE", "EAT", "ETA", "TEA", "AE", "AT", "ET", "TA", "TE"]
+        );
+        assert!(gaddag.sub_anagrams("TAE?").unwrap().contains(&"SEAT".to_string()));
+    }
+
+    #[test]
+    fn patterns() {
+        let gaddag = gaddag();
+        let matches = |pattern: &str, lengths| gaddag.pattern_matches(pattern, lengths).unwrap();
+        assert_eq!(matches("C?T", 0..=15), ["CAT"]);
+        assert_eq!(matches(".A.", 0..=15), ["CAR", "CAT", "EAR", "EAT", "OAR", "OAT", "RAT", "TAN", "TAR"]);
+        assert_eq!(matches("CA*", 0..=15), ["CAR", "CARS", "CART", "CAT", "CATS"]);
+        assert_eq!(matches("CA**", 4..=4), ["CARS", "CART", "CATS"]);
+        assert_eq!(matches("*S", 5..=5), ["COATS", "NOTES", "TONES"]);
+        assert_eq!(matches("[CR]AT", 0..=15), ["CAT", "RAT"]);
+        assert_eq!(matches("[^C]AT", 0..=15), ["EAT", "OAT", "RAT"]);
+
+        assert!(gaddag.pattern_matches("[AB", 0..=15).is_err());
+        assert!(gaddag.pattern_matches("A#", 0..=15).is_err());
+        assert!(gaddag.pattern_matches("[A1]", 0..=15).is_err());
+    }
+}
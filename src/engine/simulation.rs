@@ -0,0 +1,387 @@
+use super::{
+    actions::Action,
+    evaluator::Evaluator,
+    inference::{RackInference, deal_inferred},
+    moves::MoveGenerator,
+};
+use crate::game::{Game, rack::Rack, tile::Tile};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// monte carlo simulation of candidate plays: each iteration deals the opponent a random rack from the unseen tiles,
+// plays every candidate out a few plies with the static evaluator, and scores the spread it ends with.
+// candidates that fall clearly behind the leader stop getting simulated
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub candidates: usize, // how many of the evaluator's best plays get simulated
+    pub plies: usize,      // turns played after the candidate, 2 = one reply each
+    pub iterations: usize, // most iterations per candidate
+    pub threads: usize,
+    pub time_limit: Option<Duration>,
+    pub min_iterations: usize,            // no pruning before this many iterations
+    pub z: f64,                           // standard errors a candidate has to trail the leader by to be pruned
+    pub inference: Option<RackInference>, // opponent racks hold a leave from this instead of being drawn uniformly
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            candidates: 10,
+            plies: 2,
+            iterations: 1000,
+            threads: 1,
+            time_limit: None,
+            min_iterations: 50,
+            z: 2.576,
+            inference: None,
+        }
+    }
+}
+
+// running mean and variance of a candidate's spread (welford), and its wins
+#[derive(Debug, Clone, Default)]
+pub struct SimStats {
+    pub iterations: u32,
+    pub mean: f64,
+    m2: f64,
+    pub wins: f64, // ties count half
+}
+
+impl SimStats {
+    pub fn record(&mut self, spread: f64, win: f64) {
+        self.iterations += 1;
+        let delta = spread - self.mean;
+        self.mean += delta / self.iterations as f64;
+        self.m2 += delta * (spread - self.mean);
+        self.wins += win;
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.iterations < 2 {
+            0.0
+        } else {
+            self.m2 / (self.iterations - 1) as f64
+        }
+    }
+
+    pub fn std_error(&self) -> f64 {
+        if self.iterations == 0 {
+            f64::INFINITY
+        } else {
+            (self.variance() / self.iterations as f64).sqrt()
+        }
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.iterations == 0 { 0.0 } else { self.wins / self.iterations as f64 }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimCandidate {
+    pub action: Action,
+    pub stats: SimStats,
+    pub pruned: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimResult {
+    pub candidates: Vec<SimCandidate>, // best mean spread first
+    pub iterations: usize,
+    pub elapsed: Duration,
+}
+
+impl SimResult {
+    pub fn best(&self) -> Option<&SimCandidate> {
+        self.candidates.first()
+    }
+}
+
+/// Gives `player` a new rack of the same size drawn from the bag, after putting their old rack back in it.
+/// From the other player's side that's a random rack out of the unseen tiles.
+pub fn redeal_rack(game: &mut Game, player: usize) {
+    let size = game.racks[player].tiles().len();
+    for tile in game.racks[player].tiles() {
+        game.bag.tiles[tile.to_index() as usize] += 1;
+    }
+    game.racks[player] = Rack::new(game.bag.draw_tiles(size));
+}
+
+/// Gives `player` exactly `tiles`, which have to be in the bag once their old rack is put back.
+/// Returns false and leaves the game as it was if they aren't.
+pub fn deal_rack(game: &mut Game, player: usize, tiles: &[Tile]) -> bool {
+    let mut bag = game.bag.clone();
+    for tile in game.racks[player].tiles() {
+        bag.tiles[tile.to_index() as usize] += 1;
+    }
+    for tile in tiles {
+        let count = &mut bag.tiles[tile.to_index() as usize];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+    }
+    game.bag = bag;
+    game.racks[player] = Rack::new(tiles.to_vec());
+    true
+}
+
+// plays `action` then `plies` turns of the evaluator, gives (spread change for `player`, win)
+fn playout(game: &Game, player: usize, action: &Action, plies: usize, evaluator: &Evaluator) -> (f64, f64) {
+    let spread = |game: &Game| game.scores[player] as f64 - game.scores[1 - player] as f64;
+    let start = spread(game);
+
+    // play_action and not simulate_move: a playout needs the full turn, the mover drawing from the bag, the turn passing
+    // and end_game scoring the racks. simulate_move only puts the tiles down and has nothing for swaps or passes
+    let mut game = game.clone();
+    game.play_action(action);
+    for _ in 0..plies {
+        if game.is_over() {
+            break;
+        }
+        let reply = evaluator.choose_action(&game);
+        game.play_action(&reply);
+    }
+
+    // is_over games already had their racks counted by end_game, otherwise what's left on both racks still counts
+    let mut end = spread(&game);
+    if !game.is_over() {
+        let alphabet = game.gaddag.alphabet();
+        let leave = |player: usize| evaluator.leave_value(game.racks[player].tiles(), game.bag.len(), alphabet) as f64;
+        end += leave(player) - leave(1 - player);
+    }
+    let win = match end.partial_cmp(&0.0) {
+        Some(std::cmp::Ordering::Greater) => 1.0,
+        Some(std::cmp::Ordering::Equal) => 0.5,
+        _ => 0.0,
+    };
+    (end - start, win)
+}
+
+// prunes every candidate `z` standard errors behind the leader
+fn prune(candidates: &mut [SimCandidate], z: f64) {
+    let Some(leader) = candidates
+        .iter()
+        .filter(|candidate| !candidate.pruned)
+        .max_by(|a, b| a.stats.mean.total_cmp(&b.stats.mean))
+    else {
+        return;
+    };
+    let (mean, error) = (leader.stats.mean, leader.stats.std_error());
+    for candidate in candidates.iter_mut().filter(|candidate| !candidate.pruned) {
+        let gap = mean - candidate.stats.mean;
+        if gap > z * (error.powi(2) + candidate.stats.std_error().powi(2)).sqrt() {
+            candidate.pruned = true;
+        }
+    }
+}
+
+/// Simulates `actions` for the player to move. Every iteration deals the opponent a new rack and runs each
+/// candidate still in the running on it. Stops after `config.iterations`, at the time limit,
+/// or once one candidate is left.
+pub fn simulate(game: &Game, actions: Vec<Action>, evaluator: &Evaluator, config: &SimConfig) -> SimResult {
+    let start = Instant::now();
+    let player = game.current_player;
+    let candidates: Vec<SimCandidate> = actions
+        .into_iter()
+        .map(|action| SimCandidate {
+            action,
+            stats: SimStats::default(),
+            pruned: false,
+        })
+        .collect();
+    let state = Mutex::new((candidates, 0usize)); // candidates and iterations started
+
+    let done = |candidates: &[SimCandidate], started: usize| {
+        started >= config.iterations
+            || config.time_limit.is_some_and(|limit| start.elapsed() >= limit)
+            || candidates.iter().filter(|candidate| !candidate.pruned).count() <= 1
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..config.threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let active: Vec<(usize, Action)> = {
+                        let mut state = state.lock().unwrap();
+                        let (candidates, started) = &mut *state;
+                        if done(candidates, *started) {
+                            return;
+                        }
+                        *started += 1;
+                        candidates
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, candidate)| !candidate.pruned)
+                            .map(|(i, candidate)| (i, candidate.action.clone()))
+                            .collect()
+                    };
+
+                    // every candidate sees the same opponent rack
+                    let mut dealt = game.clone();
+                    match &config.inference {
+                        Some(inference) => deal_inferred(&mut dealt, 1 - player, inference),
+                        None => redeal_rack(&mut dealt, 1 - player),
+                    }
+                    let results: Vec<(usize, f64, f64)> = active
+                        .iter()
+                        .map(|(i, action)| {
+                            let (spread, win) = playout(&dealt, player, action, config.plies, evaluator);
+                            (*i, spread, win)
+                        })
+                        .collect();
+
+                    let mut state = state.lock().unwrap();
+                    let (candidates, _) = &mut *state;
+                    for (i, spread, win) in results {
+                        candidates[i].stats.record(spread, win);
+                    }
+                    let least = candidates
+                        .iter()
+                        .filter(|candidate| !candidate.pruned)
+                        .map(|candidate| candidate.stats.iterations)
+                        .min();
+                    if least.is_some_and(|least| least as usize >= config.min_iterations) {
+                        prune(candidates, config.z);
+                    }
+                }
+            });
+        }
+    });
+
+    let (mut candidates, started) = state.into_inner().unwrap();
+    candidates.sort_by(|a, b| a.pruned.cmp(&b.pruned).then_with(|| b.stats.mean.total_cmp(&a.stats.mean)));
+    SimResult {
+        iterations: started,
+        elapsed: start.elapsed(),
+        candidates,
+    }
+}
+
+/// Simulates the evaluator's `config.candidates` best plays for the player to move.
+pub fn simulate_top(game: &Game, evaluator: &Evaluator, config: &SimConfig) -> SimResult {
+    let generator = MoveGenerator {
+        board: game.board.clone(),
+        rack: game.racks[game.current_player].clone(),
+    };
+    let actions: Vec<Action> = evaluator
+        .rank(&generator, game.bag.len())
+        .into_iter()
+        .take(config.candidates)
+        .map(|ranked| Action::Move(ranked.mv))
+        .collect();
+    simulate(game, actions, evaluator, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::testing::{gaddag, rack},
+        game::alphabet::MAX_TILE_TYPES,
+    };
+
+    // the bag plus the player's rack, by tile type
+    fn tiles(game: &Game, player: usize) -> [u8; MAX_TILE_TYPES] {
+        let mut counts = game.bag.tiles;
+        for tile in game.racks[player].tiles() {
+            counts[tile.to_index() as usize] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn records_mean_variance_and_wins() {
+        let mut stats = SimStats::default();
+        for (spread, win) in [
+            (2.0, 1.0),
+            (4.0, 1.0),
+            (4.0, 0.5),
+            (4.0, 1.0),
+            (5.0, 1.0),
+            (5.0, 1.0),
+            (7.0, 0.0),
+            (9.0, 1.0),
+        ] {
+            stats.record(spread, win);
+        }
+        // squares off the mean of 5 add up to 32
+        assert_eq!(stats.iterations, 8);
+        assert!((stats.mean - 5.0).abs() < 1e-9);
+        assert!((stats.variance() - 32.0 / 7.0).abs() < 1e-9);
+        assert!((stats.std_error() - (32.0 / 7.0 / 8.0f64).sqrt()).abs() < 1e-9);
+        assert_eq!(stats.win_rate(), 6.5 / 8.0);
+    }
+
+    #[test]
+    fn prunes_only_what_trails_clearly() {
+        let candidate = |spreads: &[f64]| {
+            let mut stats = SimStats::default();
+            for &spread in spreads {
+                stats.record(spread, 0.0);
+            }
+            SimCandidate {
+                action: Action::Pass,
+                stats,
+                pruned: false,
+            }
+        };
+        let mut candidates = vec![
+            candidate(&[20.0, 22.0, 18.0, 20.0]),
+            candidate(&[19.0, 21.0, 17.0, 19.0]),
+            candidate(&[0.0, 2.0, -2.0, 0.0]),
+        ];
+        prune(&mut candidates, 2.576);
+        assert_eq!(
+            candidates.iter().map(|candidate| candidate.pruned).collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+    }
+
+    #[test]
+    fn stops_at_iterations_or_one_candidate() {
+        let game = Game::init(gaddag());
+        let evaluator = Evaluator::default();
+        let config = SimConfig {
+            iterations: 5,
+            min_iterations: usize::MAX,
+            ..Default::default()
+        };
+        let swap = Action::Swap(game.racks[0].tiles()[..1].to_vec());
+        let result = simulate(&game, vec![Action::Pass, swap], &evaluator, &config);
+        assert_eq!(result.iterations, 5);
+        assert!(result.candidates.iter().all(|candidate| candidate.stats.iterations == 5));
+
+        // nothing to choose between
+        let result = simulate(&game, vec![Action::Pass], &evaluator, &config);
+        assert_eq!(result.iterations, 0);
+    }
+
+    #[test]
+    fn dealing_keeps_every_tile() {
+        let mut game = Game::init(gaddag());
+        let before = tiles(&game, 1);
+        redeal_rack(&mut game, 1);
+        assert_eq!(game.racks[1].tiles().len(), 7);
+        assert_eq!(tiles(&game, 1), before);
+
+        // the other rack back in the bag, so every letter of a full bag is there to deal
+        for &tile in game.racks[0].tiles() {
+            game.bag.tiles[tile.to_index() as usize] += 1;
+        }
+        game.racks[0] = Rack::new(Vec::new());
+        let before = tiles(&game, 1);
+        assert!(deal_rack(&mut game, 1, rack("QZ?").tiles()));
+        assert_eq!(game.racks[1].tiles(), rack("QZ?").tiles());
+        assert_eq!(tiles(&game, 1), before);
+
+        // there's one Q
+        let rack_before = game.racks[1].tiles().to_vec();
+        assert!(!deal_rack(&mut game, 1, rack("QQ").tiles()));
+        assert_eq!(game.racks[1].tiles(), rack_before);
+        assert_eq!(tiles(&game, 1), before);
+    }
+}
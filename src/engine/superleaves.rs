@@ -0,0 +1,286 @@
+use super::{
+    actions::exchanges,
+    gaddag::Gaddag,
+    leaves::{LeaveKey, LeaveTable, leave_key},
+    moves::{Move, MoveGenerator},
+};
+use crate::game::{
+    Game,
+    alphabet::Alphabet,
+    bag::MIN_BAG_FOR_EXCHANGE,
+    rack::{RACK_TILES, Rack},
+    tile::Tile,
+};
+use std::{collections::HashMap, sync::Arc};
+
+// superleaves from self-play: every leave kept while the bag still has tiles is credited with what the same player
+// scores on their next turn, and a leave is worth how far that is above the average turn.
+// leaves that are rare or never seen lean on the leaves one tile shorter, so every 1-6 tile leave gets a value
+
+// points scored on the turn after each leave, summed per leave
+#[derive(Debug, Clone, Default)]
+pub struct LeaveStats {
+    pub leaves: HashMap<LeaveKey, (f64, u32)>,
+    pub total: f64,
+    pub samples: u64,
+}
+
+impl LeaveStats {
+    pub fn record(&mut self, leave: LeaveKey, points: u16) {
+        let entry = self.leaves.entry(leave).or_default();
+        entry.0 += points as f64;
+        entry.1 += 1;
+        self.total += points as f64;
+        self.samples += 1;
+    }
+
+    pub fn merge(&mut self, other: LeaveStats) {
+        for (leave, (sum, count)) in other.leaves {
+            let entry = self.leaves.entry(leave).or_default();
+            entry.0 += sum;
+            entry.1 += count;
+        }
+        self.total += other.total;
+        self.samples += other.samples;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.total / self.samples as f64 }
+    }
+}
+
+// what a self-play player does with its turn, and the tiles it keeps doing it
+enum Turn {
+    Play(Move, Rack),
+    Exchange(Vec<Tile>, Rack),
+    Pass,
+}
+
+// best equity play, or exchange if keeping the leave is worth more than any play
+fn choose_turn(game: &Game, leaves: &LeaveTable) -> Turn {
+    let rack = game.racks[game.current_player].clone();
+    let generator = MoveGenerator {
+        board: game.board.clone(),
+        rack: rack.clone(),
+    };
+
+    let mut best = generator.top_moves_by_equity(1, leaves).pop().map(|ranked| {
+        let leave = ranked.mv.leave(&rack);
+        (ranked.equity, Turn::Play(ranked.mv, leave))
+    });
+
+    if game.bag.len() >= MIN_BAG_FOR_EXCHANGE {
+        for exchange in exchanges(&rack) {
+            let mut kept = rack.clone();
+            for &tile in &exchange {
+                kept.remove_tile(tile);
+            }
+            let equity = leaves.value(kept.tiles());
+            if best.as_ref().is_none_or(|(best_equity, _)| equity > *best_equity) {
+                best = Some((equity, Turn::Exchange(exchange, kept)));
+            }
+        }
+    }
+
+    best.map_or(Turn::Pass, |(_, turn)| turn)
+}
+
+/// Plays one game with both players on `leaves`, and records every leave kept while tiles were still drawn.
+pub fn play_game(game: &mut Game, leaves: &LeaveTable, stats: &mut LeaveStats) {
+    let mut pending: [Option<LeaveKey>; 2] = [None, None];
+    while !game.is_over() {
+        let player = game.current_player;
+        let (points, kept) = match choose_turn(game, leaves) {
+            Turn::Play(mv, kept) => {
+                let points = mv.score;
+                game.play_move(&mv);
+                (points, Some(kept))
+            }
+            Turn::Exchange(tiles, kept) => {
+                game.exchange(tiles);
+                (0, Some(kept))
+            }
+            Turn::Pass => {
+                game.pass_turn();
+                (0, None)
+            }
+        };
+
+        if let Some(leave) = pending[player].take() {
+            stats.record(leave, points);
+        }
+        // only leaves that get drawn to say something about the next turn
+        if let Some(kept) = kept
+            && (1..RACK_TILES).contains(&kept.tiles().len())
+            && !game.bag.is_empty()
+        {
+            pending[player] = Some(leave_key(kept.tiles()));
+        }
+    }
+}
+
+/// `games` self-play games spread over `threads` threads, all using `leaves`.
+pub fn self_play(gaddag: &Arc<Gaddag>, leaves: &LeaveTable, games: usize, threads: usize) -> LeaveStats {
+    let threads = threads.clamp(1, games.max(1));
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                // the first threads take the remainder
+                let count = games / threads + usize::from(thread < games % threads);
+                scope.spawn(move || {
+                    let mut stats = LeaveStats::default();
+                    for _ in 0..count {
+                        play_game(&mut Game::init(gaddag.clone()), leaves, &mut stats);
+                    }
+                    stats
+                })
+            })
+            .collect();
+
+        let mut stats = LeaveStats::default();
+        for handle in handles {
+            stats.merge(handle.join().unwrap());
+        }
+        stats
+    })
+}
+
+// every leave of 1 to RACK_TILES - 1 tiles the alphabet's bag can give, shortest first
+pub fn all_leaves(alphabet: &Alphabet) -> Vec<LeaveKey> {
+    fn extend(counts: &[u8], from: usize, leave: &mut LeaveKey, out: &mut Vec<LeaveKey>) {
+        if !leave.is_empty() {
+            out.push(leave.clone());
+        }
+        if leave.len() == RACK_TILES - 1 {
+            return;
+        }
+        for index in from..counts.len() {
+            let used = leave.iter().filter(|&&tile| tile as usize == index).count();
+            if used < counts[index] as usize {
+                leave.push(index as u8);
+                extend(counts, index, leave, out);
+                leave.pop();
+            }
+        }
+    }
+
+    let distribution = alphabet.distribution();
+    let mut out = Vec::new();
+    extend(&distribution[..alphabet.len() + 1], 0, &mut LeaveKey::new(), &mut out);
+    out.sort_by_key(|leave| leave.len());
+    out
+}
+
+/// Values for every leave from the recorded stats. A leave seen `n` times gets
+/// (n * observed + smoothing * prior) / (n + smoothing), where observed is its mean next turn minus the overall mean,
+/// and prior is the leave one tile shorter plus that tile on its own, averaged over which tile (0 for single tiles).
+pub fn fit(stats: &LeaveStats, alphabet: &Alphabet, smoothing: f64) -> LeaveTable {
+    let mean = stats.mean();
+    let mut table = LeaveTable::default();
+    for leave in all_leaves(alphabet) {
+        let prior = if leave.len() == 1 {
+            0.0
+        } else {
+            let mut sum = 0.0;
+            let mut count = 0;
+            for i in 0..leave.len() {
+                if i > 0 && leave[i] == leave[i - 1] {
+                    continue;
+                }
+                let mut shorter = leave.clone();
+                let tile = shorter.remove(i);
+                let value = |key: &[u8]| table.values.get(key).copied().unwrap_or(0.0) as f64;
+                sum += value(&shorter) + value(&[tile]);
+                count += 1;
+            }
+            sum / count as f64
+        };
+
+        let value = match stats.leaves.get(&leave) {
+            Some(&(sum, n)) => {
+                let observed = sum / n as f64 - mean;
+                (n as f64 * observed + smoothing * prior) / (n as f64 + smoothing)
+            }
+            None => prior,
+        };
+        table.values.insert(leave, value as f32);
+    }
+    table
+}
+
+/// Builds a leave table for the lexicon from scratch: the first round plays on score alone,
+/// every later round plays on the table the round before it fitted.
+pub fn generate(gaddag: &Arc<Gaddag>, rounds: usize, games_per_round: usize, threads: usize, smoothing: f64) -> LeaveTable {
+    let mut table = LeaveTable::default();
+    for _ in 0..rounds {
+        let stats = self_play(gaddag, &table, games_per_round, threads);
+        table = fit(&stats, gaddag.alphabet(), smoothing);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        engine::testing::gaddag,
+        game::alphabet::{Letter, MAX_TILE_TYPES},
+    };
+    use smallvec::smallvec;
+
+    // two A, a B and a blank, tile indices 1, 2 and 0
+    fn tiny() -> Alphabet {
+        Alphabet::new("tiny", vec![Letter::new("A", 1, 2), Letter::new("B", 3, 1)], 1).unwrap()
+    }
+
+    #[test]
+    fn fits_towards_the_shorter_leaves() {
+        let (a, b): (LeaveKey, LeaveKey) = (smallvec![1], smallvec![2]);
+        let mut stats = LeaveStats::default();
+        for (leave, points) in [(a.clone(), 12), (a, 12), (b.clone(), 6), (b, 6), (smallvec![1, 1], 9)] {
+            stats.record(leave, points);
+        }
+        assert_eq!(stats.mean(), 9.0);
+
+        let table = fit(&stats, &tiny(), 2.0);
+        let value = |key: &[u8]| table.values[key];
+        // seen twice, 3 over the mean, pulled halfway to 0
+        assert_eq!(value(&[1]), 1.5);
+        assert_eq!(value(&[2]), -1.5);
+        assert_eq!(value(&[0]), 0.0);
+        // seen once at the mean, a third of the way from A + A
+        assert_eq!(value(&[1, 1]), 2.0);
+        // never seen: the average of AB + A and AA + B
+        assert_eq!(value(&[1, 2]), 0.0);
+        assert_eq!(value(&[1, 1, 2]), 1.0);
+        assert_eq!(value(&[0, 1]), 1.5);
+        assert_eq!(table.values.len(), all_leaves(&tiny()).len());
+    }
+
+    #[test]
+    fn self_play_keeps_every_tile() {
+        let gaddag = gaddag();
+        let mut game = Game::init(gaddag.clone());
+        let mut stats = LeaveStats::default();
+        play_game(&mut game, &LeaveTable::default(), &mut stats);
+        assert!(game.is_over());
+
+        let mut counts = game.bag.tiles;
+        for rack in &game.racks {
+            for tile in rack.tiles() {
+                counts[tile.to_index() as usize] += 1;
+            }
+        }
+        for (_, tile) in game.board.tiles() {
+            counts[tile.to_index() as usize] += 1;
+        }
+        let full: [u8; MAX_TILE_TYPES] = gaddag.alphabet().distribution();
+        assert_eq!(counts, full);
+
+        let recorded: u32 = stats.leaves.values().map(|&(_, count)| count).sum();
+        let points: f64 = stats.leaves.values().map(|&(sum, _)| sum).sum();
+        assert_eq!(recorded as u64, stats.samples);
+        assert_eq!(points, stats.total);
+        assert!(stats.leaves.keys().all(|leave| (1..RACK_TILES).contains(&leave.len())));
+    }
+}
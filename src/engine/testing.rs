@@ -0,0 +1,52 @@
+use super::gaddag::Gaddag;
+use crate::{
+    Direction, Pos,
+    game::{alphabet::Alphabet, board::Board, rack::Rack, tile::Tile},
+};
+use std::sync::Arc;
+
+// a small english lexicon for the tests, with a few bingos for AENORST
+const WORDS: &[&str] = &[
+    "AA", "AB", "AD", "AE", "AN", "AR", "AS", "AT", "BA", "BE", "DA", "DE", "DO", "ED", "EN", "ER", "ES", "ET", "NA", "NE", "NO", "OE", "ON", "OR",
+    "OS", "RE", "SO", "TA", "TE", "TO", "ACT", "ACTS", "ARE", "ART", "ARTS", "ATE", "ATONE", "ATONES", "CAR", "CARS", "CART", "CAT", "CATS", "COAT",
+    "COATS", "CORE", "EAR", "EARN", "EAT", "EATS", "ERA", "ETA", "NET", "NOT", "NOTE", "NOTES", "OAR", "OAT", "OATS", "ONE", "ONES", "ORE", "RAT",
+    "RATE", "RATS", "ROE", "ROT", "ROTE", "SCAT", "SEA", "SEAT", "SENATOR", "SET", "SNORT", "SON", "STAR", "STONE", "STONER", "STORE", "TAN", "TAR",
+    "TEA", "TEN", "TOE", "TOES", "TON", "TONE", "TONER", "TONES", "TORE", "TREASON",
+];
+
+pub fn gaddag() -> Arc<Gaddag> {
+    Arc::new(Gaddag::from_bytes(WORDS.join("\n").as_bytes(), Alphabet::english()).unwrap())
+}
+
+// ? for a blank
+pub fn rack(letters: &str) -> Rack {
+    Rack::new(
+        letters
+            .bytes()
+            .map(|letter| if letter == b'?' { Tile::blank(None) } else { Tile::letter(letter) })
+            .collect(),
+    )
+}
+
+pub fn place_word(board: &mut Board, word: &str, pos: Pos, direction: Direction) {
+    for (i, letter) in word.bytes().enumerate() {
+        let pos = match direction {
+            Direction::Horizontal => Pos::new(pos.row, pos.col + i),
+            Direction::Vertical => Pos::new(pos.row + i, pos.col),
+        };
+        board.place_tile(pos, Tile::letter(letter));
+    }
+}
+
+// empty, CAT across the centre, ATONE down from its A, ONES across from the O
+pub fn boards() -> Vec<Board> {
+    let mut board = Board::new();
+    let mut boards = vec![board.clone()];
+    place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+    boards.push(board.clone());
+    place_word(&mut board, "ATONE", Pos::new(7, 8), Direction::Vertical);
+    boards.push(board.clone());
+    place_word(&mut board, "ONES", Pos::new(9, 8), Direction::Horizontal);
+    boards.push(board);
+    boards
+}
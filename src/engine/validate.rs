@@ -0,0 +1,265 @@
+use super::{
+    gaddag::Gaddag,
+    moves::{Move, PlayedTile},
+};
+use crate::{
+    Direction, Pos,
+    game::{
+        board::{BOARD_SIZE, Board},
+        rack::Rack,
+        tile::Tile,
+    },
+};
+use smallvec::SmallVec;
+use std::fmt;
+
+// why a set of placements isn't a legal play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlacementError {
+    NoTiles,
+    OffBoard(Pos),
+    Occupied(Pos),
+    DuplicatePosition(Pos),
+    EmptyTile(Pos),
+    UndesignatedBlank(Pos), // a blank has to say what letter it is
+    NotOnRack(Tile),
+    NotInLine,
+    Gap(Pos), // first empty square between the placed tiles
+    MissesCenter,
+    NotConnected,
+    NoWord,               // a lone tile on an empty board
+    Phonies(Vec<String>), // every formed word that isn't in the lexicon
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementError::NoTiles => write!(f, "no tiles placed"),
+            PlacementError::OffBoard(pos) => write!(f, "{:?} is off the board", pos),
+            PlacementError::Occupied(pos) => write!(f, "{:?} already has a tile", pos),
+            PlacementError::DuplicatePosition(pos) => write!(f, "more than one tile placed on {:?}", pos),
+            PlacementError::EmptyTile(pos) => write!(f, "empty tile placed on {:?}", pos),
+            PlacementError::UndesignatedBlank(pos) => write!(f, "blank on {:?} has no letter", pos),
+            PlacementError::NotOnRack(tile) => write!(f, "{:?} is not on the rack", tile),
+            PlacementError::NotInLine => write!(f, "tiles are not in a single row or column"),
+            PlacementError::Gap(pos) => write!(f, "gap at {:?}", pos),
+            PlacementError::MissesCenter => write!(f, "the first move has to cover the centre square"),
+            PlacementError::NotConnected => write!(f, "tiles don't touch any tile on the board"),
+            PlacementError::NoWord => write!(f, "no word of two or more letters is formed"),
+            PlacementError::Phonies(words) => write!(f, "not in the lexicon: {}", words.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// Checks tiles put down by hand, in any order, and turns them into a scored `Move` along the main word.
+/// Blanks have to carry the letter they stand for (`Tile::blank(Some(letter))`).
+/// A single tile reads horizontally if it has a horizontal neighbour, vertically otherwise, like the generator.
+pub fn validate_placement(board: &Board, rack: &Rack, placements: &[(Pos, Tile)], gaddag: &Gaddag) -> Result<Move, PlacementError> {
+    if placements.is_empty() {
+        return Err(PlacementError::NoTiles);
+    }
+
+    let mut rack = rack.clone();
+    for (i, &(pos, tile)) in placements.iter().enumerate() {
+        if pos.row >= BOARD_SIZE || pos.col >= BOARD_SIZE {
+            return Err(PlacementError::OffBoard(pos));
+        }
+        if board.is_occupied(pos) {
+            return Err(PlacementError::Occupied(pos));
+        }
+        if placements[..i].iter().any(|&(other, _)| other == pos) {
+            return Err(PlacementError::DuplicatePosition(pos));
+        }
+        if tile.is_empty() {
+            return Err(PlacementError::EmptyTile(pos));
+        }
+        if tile.is_blank() && tile.byte() == b'*' {
+            return Err(PlacementError::UndesignatedBlank(pos));
+        }
+        if !rack.remove_tile(tile) {
+            return Err(PlacementError::NotOnRack(tile));
+        }
+    }
+
+    let first = placements[0].0;
+    let same_row = placements.iter().all(|(pos, _)| pos.row == first.row);
+    let same_col = placements.iter().all(|(pos, _)| pos.col == first.col);
+    let has_horizontal_neighbor = || [-1, 1].iter().any(|&d| first.offset(0, d).is_some_and(|pos| board.is_occupied(pos)));
+    let direction = match (same_row, same_col) {
+        (true, false) => Direction::Horizontal,
+        (false, true) => Direction::Vertical,
+        (true, true) if has_horizontal_neighbor() => Direction::Horizontal,
+        (true, true) => Direction::Vertical,
+        (false, false) => return Err(PlacementError::NotInLine),
+    };
+
+    // along the line, (fixed, varying)
+    let line = |pos: Pos| match direction {
+        Direction::Horizontal => (pos.row, pos.col),
+        Direction::Vertical => (pos.col, pos.row),
+    };
+    let at = |fixed: usize, i: usize| match direction {
+        Direction::Horizontal => Pos::new(fixed, i),
+        Direction::Vertical => Pos::new(i, fixed),
+    };
+    let fixed = line(first).0;
+    let start = placements.iter().map(|&(pos, _)| line(pos).1).min().unwrap();
+    let end = placements.iter().map(|&(pos, _)| line(pos).1).max().unwrap();
+    for i in start..=end {
+        let pos = at(fixed, i);
+        if !board.is_occupied(pos) && !placements.iter().any(|&(placed, _)| placed == pos) {
+            return Err(PlacementError::Gap(pos));
+        }
+    }
+
+    if board.is_empty() {
+        let center = Pos::new(BOARD_SIZE / 2, BOARD_SIZE / 2);
+        if !placements.iter().any(|&(pos, _)| pos == center) {
+            return Err(PlacementError::MissesCenter);
+        }
+    } else {
+        let touches = placements.iter().any(|&(pos, _)| {
+            [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .any(|&(d_row, d_col)| pos.offset(d_row, d_col).is_some_and(|neighbor| board.is_occupied(neighbor)))
+        });
+        if !touches {
+            return Err(PlacementError::NotConnected);
+        }
+    }
+
+    // the move spans the whole main word, board tiles included
+    let mut after = board.clone();
+    for &(pos, tile) in placements {
+        after.place_tile(pos, tile);
+    }
+    let main = after.word_at(first, direction);
+    let tiles_data: SmallVec<[PlayedTile; 7]> = main
+        .iter()
+        .map(|&(pos, tile)| {
+            if board.is_occupied(pos) {
+                PlayedTile::Board(tile)
+            } else {
+                PlayedTile::Rack(tile)
+            }
+        })
+        .collect();
+    let mut mv = Move {
+        tiles_data,
+        pos: main[0].0,
+        direction,
+        score: 0,
+    };
+
+    let breakdown = mv.breakdown(board, gaddag.alphabet());
+    if breakdown.words.is_empty() {
+        return Err(PlacementError::NoWord);
+    }
+    let phonies: Vec<String> = breakdown
+        .words
+        .iter()
+        .filter(|word| !gaddag.contains(&word.word.bytes()))
+        .map(|word| word.letters.clone())
+        .collect();
+    if !phonies.is_empty() {
+        return Err(PlacementError::Phonies(phonies));
+    }
+
+    mv.score = breakdown.total;
+    Ok(mv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        moves::MoveGenerator,
+        testing::{boards, gaddag, place_word, rack},
+    };
+
+    fn letter(letter: u8) -> Tile {
+        Tile::letter(letter)
+    }
+
+    #[test]
+    fn rejects_illegal_placements() {
+        let gaddag = gaddag();
+        let empty = Board::new();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let rack = rack("AERST?");
+        let check = |board: &Board, placements: &[(Pos, Tile)]| validate_placement(board, &rack, placements, &gaddag).err();
+
+        assert_eq!(check(&board, &[]), Some(PlacementError::NoTiles));
+        assert_eq!(
+            check(&board, &[(Pos::new(15, 0), letter(b'A'))]),
+            Some(PlacementError::OffBoard(Pos::new(15, 0)))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(7, 8), letter(b'A'))]),
+            Some(PlacementError::Occupied(Pos::new(7, 8)))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 8), letter(b'S')), (Pos::new(8, 8), letter(b'A'))]),
+            Some(PlacementError::DuplicatePosition(Pos::new(8, 8)))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 8), Tile::empty())]),
+            Some(PlacementError::EmptyTile(Pos::new(8, 8)))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 8), Tile::blank(None))]),
+            Some(PlacementError::UndesignatedBlank(Pos::new(8, 8)))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 8), letter(b'Z'))]),
+            Some(PlacementError::NotOnRack(letter(b'Z')))
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 8), letter(b'S')), (Pos::new(9, 9), letter(b'A'))]),
+            Some(PlacementError::NotInLine)
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 7), letter(b'A')), (Pos::new(8, 9), letter(b'S'))]),
+            Some(PlacementError::Gap(Pos::new(8, 8)))
+        );
+        assert_eq!(
+            check(&empty, &[(Pos::new(6, 7), letter(b'A')), (Pos::new(6, 8), letter(b'T'))]),
+            Some(PlacementError::MissesCenter)
+        );
+        assert_eq!(
+            check(&board, &[(Pos::new(0, 0), letter(b'A')), (Pos::new(0, 1), letter(b'T'))]),
+            Some(PlacementError::NotConnected)
+        );
+        assert_eq!(check(&empty, &[(Pos::new(7, 7), letter(b'A'))]), Some(PlacementError::NoWord));
+        // SA across, and TS down from the T of CAT
+        assert_eq!(
+            check(&board, &[(Pos::new(8, 9), letter(b'S')), (Pos::new(8, 10), letter(b'A'))]),
+            Some(PlacementError::Phonies(vec!["SA".to_string(), "TS".to_string()]))
+        );
+    }
+
+    #[test]
+    fn generated_moves_validate_to_themselves() {
+        let gaddag = gaddag();
+        for board in boards() {
+            let rack = rack("AENRST?");
+            for mv in MoveGenerator::run(board.clone(), rack.clone(), gaddag.clone()) {
+                // given back to front, so the order doesn't matter
+                let mut placements: Vec<(Pos, Tile)> = mv
+                    .tile_positions()
+                    .filter_map(|(pos, played_tile)| match played_tile {
+                        PlayedTile::Rack(tile) => Some((pos, tile)),
+                        PlayedTile::Board(_) => None,
+                    })
+                    .collect();
+                placements.reverse();
+                let validated = validate_placement(&board, &rack, &placements, &gaddag).unwrap();
+                assert_eq!(validated, mv);
+                assert_eq!(validated.score, mv.score);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use super::moves::{Move, PlayedTile};
+use crate::{
+    Direction, Pos,
+    game::{
+        alphabet::Alphabet,
+        board::{Board, Multiplier},
+        rack::RACK_TILES,
+    },
+};
+use std::fmt;
+
+pub const BINGO_BONUS: u16 = 50;
+
+// a word on the board made by a move, the main word or one of the cross words.
+// Rack tiles are the ones the move puts down, only those get premium squares
+#[derive(Debug, Clone)]
+pub struct FormedWord {
+    pub tiles: Vec<(Pos, PlayedTile)>,
+    pub direction: Direction,
+}
+
+impl FormedWord {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.tiles.iter().map(|(_, tile)| tile.tile().byte()).collect()
+    }
+
+    pub fn get_word_string(&self, alphabet: &Alphabet) -> String {
+        self.tiles.iter().map(|(_, tile)| alphabet.byte_label(tile.tile().byte())).collect()
+    }
+
+    pub fn score(&self, board: &Board, alphabet: &Alphabet) -> u16 {
+        let mut letters = 0u16;
+        let mut word_multiplier = 1u16;
+        for &(pos, played_tile) in &self.tiles {
+            let points = alphabet.points(played_tile.tile()) as u16;
+            letters += match (played_tile, board.get_multiplier(pos)) {
+                (PlayedTile::Rack(_), Some(Multiplier::DoubleLetter)) => points * 2,
+                (PlayedTile::Rack(_), Some(Multiplier::TripleLetter)) => points * 3,
+                (PlayedTile::Rack(_), Some(Multiplier::DoubleWord)) => {
+                    word_multiplier *= 2;
+                    points
+                }
+                (PlayedTile::Rack(_), Some(Multiplier::TripleWord)) => {
+                    word_multiplier *= 3;
+                    points
+                }
+                _ => points,
+            };
+        }
+        letters * word_multiplier
+    }
+
+    // premium squares under the move's tiles, board tiles have used theirs up
+    pub fn premiums(&self, board: &Board) -> Vec<(Pos, Multiplier)> {
+        self.tiles
+            .iter()
+            .filter(|(_, played_tile)| matches!(played_tile, PlayedTile::Rack(_)))
+            .filter_map(|&(pos, _)| board.get_multiplier(pos).map(|multiplier| (pos, multiplier)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WordScore {
+    pub word: FormedWord,
+    pub letters: String,
+    pub premiums: Vec<(Pos, Multiplier)>,
+    pub score: u16,
+}
+
+// how a move scores: the main word, then each cross word, then the bingo bonus
+#[derive(Debug, Clone)]
+pub struct ScoreBreakdown {
+    pub words: Vec<WordScore>,
+    pub bingo: u16,
+    pub total: u16,
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for word in &self.words {
+            let direction = match word.word.direction {
+                Direction::Horizontal => "across",
+                Direction::Vertical => "down",
+            };
+            write!(f, "{} {}", word.letters, direction)?;
+            for (pos, multiplier) in &word.premiums {
+                let label = match multiplier {
+                    Multiplier::DoubleLetter => "DL",
+                    Multiplier::TripleLetter => "TL",
+                    Multiplier::DoubleWord => "DW",
+                    Multiplier::TripleWord => "TW",
+                };
+                write!(f, " {}@{},{}", label, pos.row, pos.col)?;
+            }
+            writeln!(f, ": {}", word.score)?;
+        }
+        if self.bingo > 0 {
+            writeln!(f, "bingo: {}", self.bingo)?;
+        }
+        write!(f, "total: {}", self.total)
+    }
+}
+
+impl Move {
+    /// Every word the move forms on `board` (the board before the move) with its premiums and subtotal.
+    /// The total is what the generator gives the move.
+    pub fn breakdown(&self, board: &Board, alphabet: &Alphabet) -> ScoreBreakdown {
+        let words: Vec<WordScore> = formed_words(board, self)
+            .into_iter()
+            .map(|word| WordScore {
+                letters: word.get_word_string(alphabet),
+                premiums: word.premiums(board),
+                score: word.score(board, alphabet),
+                word,
+            })
+            .collect();
+
+        let tiles_placed = self.tiles_data.iter().filter(|tile| matches!(tile, PlayedTile::Rack(_))).count();
+        let bingo = if tiles_placed == RACK_TILES { BINGO_BONUS } else { 0 };
+        let total = words.iter().map(|word| word.score).sum::<u16>() + bingo;
+        ScoreBreakdown { words, bingo, total }
+    }
+}
+
+pub fn other_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::Horizontal => Direction::Vertical,
+        Direction::Vertical => Direction::Horizontal,
+    }
+}
+
+/// Every word of two or more letters that `mv` forms on `board` (the board before the move), main word first.
+/// Board tiles are taken from the board, so play-through placeholders in the move don't matter.
+pub fn formed_words(board: &Board, mv: &Move) -> Vec<FormedWord> {
+    let mut after = board.clone();
+    let mut placed = Vec::new();
+    for (pos, played_tile) in mv.tile_positions() {
+        if let PlayedTile::Rack(tile) = played_tile {
+            after.place_tile(pos, tile);
+            placed.push(pos);
+        }
+    }
+
+    let Some(&first) = placed.first() else {
+        return Vec::new();
+    };
+
+    // tiles that weren't on the board before are the move's
+    let word_at = |pos: Pos, direction: Direction| -> Vec<(Pos, PlayedTile)> {
+        after
+            .word_at(pos, direction)
+            .into_iter()
+            .map(|(pos, tile)| {
+                (
+                    pos,
+                    if board.is_occupied(pos) {
+                        PlayedTile::Board(tile)
+                    } else {
+                        PlayedTile::Rack(tile)
+                    },
+                )
+            })
+            .collect()
+    };
+
+    let mut words = Vec::new();
+    let main = word_at(first, mv.direction);
+    if main.len() > 1 {
+        words.push(FormedWord {
+            tiles: main,
+            direction: mv.direction,
+        });
+    }
+
+    let cross_direction = other_direction(mv.direction);
+    for pos in placed {
+        let cross = word_at(pos, cross_direction);
+        if cross.len() > 1 {
+            words.push(FormedWord {
+                tiles: cross,
+                direction: cross_direction,
+            });
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{
+        moves::MoveGenerator,
+        testing::{boards, gaddag, place_word, rack},
+    };
+
+    #[test]
+    fn totals_match_generator_scores() {
+        let gaddag = gaddag();
+        let mut bingos = 0;
+        for board in boards() {
+            for letters in ["AENORST", "AENRST?", "CATS"] {
+                for mv in MoveGenerator::run(board.clone(), rack(letters), gaddag.clone()) {
+                    let breakdown = mv.breakdown(&board, gaddag.alphabet());
+                    assert_eq!(breakdown.total, mv.score, "{}", breakdown);
+                    if breakdown.bingo > 0 {
+                        bingos += 1;
+                    }
+                }
+            }
+        }
+        assert!(bingos > 0);
+    }
+
+    #[test]
+    fn lists_words_and_premiums() {
+        let gaddag = gaddag();
+        let mut board = Board::new();
+        place_word(&mut board, "CAT", Pos::new(7, 7), Direction::Horizontal);
+        let mv = MoveGenerator::run(board.clone(), rack("S"), gaddag.clone())
+            .into_iter()
+            .find(|mv| mv.get_word_string(gaddag.alphabet()) == "AS")
+            .unwrap();
+
+        let breakdown = mv.breakdown(&board, gaddag.alphabet());
+        assert_eq!(breakdown.words.len(), 1);
+        assert_eq!(breakdown.words[0].letters, "AS");
+        assert_eq!(breakdown.words[0].premiums, vec![(Pos::new(8, 8), Multiplier::DoubleLetter)]);
+        assert_eq!(breakdown.words[0].score, 3);
+        assert_eq!(breakdown.bingo, 0);
+        assert_eq!(breakdown.total, 3);
+    }
+}
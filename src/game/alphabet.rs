@@ -0,0 +1,308 @@
+use super::tile::Tile;
+
+// letters are identified by their index in the alphabet. tiles store index + 1 (see Tile),
+// and the gaddag/dawg store the byte b'A' + index, so english words end up as plain ascii
+pub const MAX_LETTERS: usize = 48;
+pub const MAX_TILE_TYPES: usize = MAX_LETTERS + 1; // letters plus the blank, which is always index 0
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Letter {
+    pub label: String, // "A", "CH", "Ñ"
+    pub points: u8,
+    pub count: u8, // tiles in a full bag
+}
+
+impl Letter {
+    pub fn new(label: &str, points: u8, count: u8) -> Self {
+        Letter {
+            label: label.to_uppercase(),
+            points,
+            count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    pub name: String,
+    letters: Vec<Letter>,
+    blanks: u8,
+    labels_by_length: Vec<(usize, Vec<char>)>, // (letter index, label chars), longest labels first for greedy matching
+}
+
+impl Alphabet {
+    pub fn new(name: &str, letters: Vec<Letter>, blanks: u8) -> Result<Self, String> {
+        if letters.is_empty() || letters.len() > MAX_LETTERS {
+            return Err(format!("an alphabet needs between 1 and {} letters, got {}", MAX_LETTERS, letters.len()));
+        }
+        for (i, letter) in letters.iter().enumerate() {
+            if letter.label.is_empty() || letter.label.chars().any(|c| c.is_whitespace() || c == '?' || c == '.') {
+                return Err(format!("invalid letter label {:?}", letter.label));
+            }
+            if letters[..i].iter().any(|other| other.label == letter.label) {
+                return Err(format!("duplicate letter {}", letter.label));
+            }
+        }
+
+        let mut labels_by_length: Vec<(usize, Vec<char>)> = letters
+            .iter()
+            .enumerate()
+            .map(|(i, letter)| (i, letter.label.chars().collect()))
+            .collect();
+        labels_by_length.sort_by_key(|(_, label)| std::cmp::Reverse(label.len()));
+
+        Ok(Alphabet {
+            name: name.to_string(),
+            letters,
+            blanks,
+            labels_by_length,
+        })
+    }
+
+    fn builtin(name: &str, letters: &[(&str, u8, u8)], blanks: u8) -> Self {
+        let letters = letters.iter().map(|&(label, points, count)| Letter::new(label, points, count)).collect();
+        Alphabet::new(name, letters, blanks).unwrap()
+    }
+
+    pub fn english() -> Self {
+        #[rustfmt::skip]
+        let letters = [
+            ("A", 1, 9), ("B", 3, 2), ("C", 3, 2), ("D", 2, 4), ("E", 1, 12), ("F", 4, 2), ("G", 2, 3), ("H", 4, 2), ("I", 1, 9),
+            ("J", 8, 1), ("K", 5, 1), ("L", 1, 4), ("M", 3, 2), ("N", 1, 6), ("O", 1, 8), ("P", 3, 2), ("Q", 10, 1), ("R", 1, 6),
+            ("S", 1, 4), ("T", 1, 6), ("U", 1, 4), ("V", 4, 2), ("W", 4, 2), ("X", 8, 1), ("Y", 4, 2), ("Z", 10, 1),
+        ];
+        Alphabet::builtin("English", &letters, 2)
+    }
+
+    pub fn spanish() -> Self {
+        #[rustfmt::skip]
+        let letters = [
+            ("A", 1, 12), ("B", 3, 2), ("C", 3, 4), ("CH", 5, 1), ("D", 2, 5), ("E", 1, 12), ("F", 4, 1), ("G", 2, 2), ("H", 4, 2),
+            ("I", 1, 6), ("J", 8, 1), ("L", 1, 4), ("LL", 8, 1), ("M", 3, 2), ("N", 1, 5), ("Ñ", 8, 1), ("O", 1, 9), ("P", 3, 2),
+            ("Q", 5, 1), ("R", 1, 5), ("RR", 8, 1), ("S", 1, 6), ("T", 1, 4), ("U", 1, 5), ("V", 4, 1), ("X", 8, 1), ("Y", 4, 1),
+            ("Z", 10, 1),
+        ];
+        Alphabet::builtin("Spanish", &letters, 2)
+    }
+
+    pub fn german() -> Self {
+        #[rustfmt::skip]
+        let letters = [
+            ("A", 1, 5), ("Ä", 6, 1), ("B", 3, 2), ("C", 4, 2), ("D", 1, 4), ("E", 1, 15), ("F", 4, 2), ("G", 2, 3), ("H", 2, 4),
+            ("I", 1, 6), ("J", 6, 1), ("K", 4, 2), ("L", 2, 3), ("M", 3, 4), ("N", 1, 9), ("O", 2, 3), ("Ö", 8, 1), ("P", 4, 1),
+            ("Q", 10, 1), ("R", 1, 6), ("S", 1, 7), ("T", 1, 6), ("U", 1, 6), ("Ü", 6, 1), ("V", 6, 1), ("W", 3, 1), ("X", 8, 1),
+            ("Y", 10, 1), ("Z", 3, 1),
+        ];
+        Alphabet::builtin("German", &letters, 2)
+    }
+
+    pub fn polish() -> Self {
+        #[rustfmt::skip]
+        let letters = [
+            ("A", 1, 9), ("Ą", 5, 1), ("B", 3, 2), ("C", 2, 3), ("Ć", 6, 1), ("D", 2, 3), ("E", 1, 7), ("Ę", 5, 1), ("F", 5, 1),
+            ("G", 3, 2), ("H", 3, 2), ("I", 1, 8), ("J", 3, 2), ("K", 2, 3), ("L", 2, 3), ("Ł", 3, 2), ("M", 2, 3), ("N", 1, 5),
+            ("Ń", 7, 1), ("O", 1, 6), ("Ó", 5, 1), ("P", 2, 3), ("R", 1, 4), ("S", 1, 4), ("Ś", 5, 1), ("T", 2, 3), ("U", 3, 2),
+            ("W", 1, 4), ("Y", 2, 4), ("Z", 1, 5), ("Ź", 9, 1), ("Ż", 5, 1),
+        ];
+        Alphabet::builtin("Polish", &letters, 2)
+    }
+
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    pub fn letters(&self) -> &[Letter] {
+        &self.letters
+    }
+
+    pub fn blanks(&self) -> u8 {
+        self.blanks
+    }
+
+    // bitmask with a bit for every letter, same layout as CrossCheck and Rack masks
+    pub fn all_letters_mask(&self) -> u64 {
+        (1u64 << self.letters.len()) - 1
+    }
+
+    // machine letters as used in the gaddag, tiles and cross checks
+    pub fn letter_byte(&self, index: usize) -> u8 {
+        b'A' + index as u8
+    }
+
+    pub fn letter_index(&self, byte: u8) -> Option<usize> {
+        let index = byte.checked_sub(b'A')? as usize;
+        if index < self.letters.len() { Some(index) } else { None }
+    }
+
+    pub fn byte_label(&self, byte: u8) -> &str {
+        match self.letter_index(byte) {
+            Some(index) => &self.letters[index].label,
+            None => "?",
+        }
+    }
+
+    pub fn points(&self, tile: Tile) -> u8 {
+        if tile.is_empty() || tile.is_blank() {
+            return 0;
+        }
+        self.letter_index(tile.byte()).map(|index| self.letters[index].points).unwrap_or(0)
+    }
+
+    // blanks are lowercase, undesignated blanks are ?
+    pub fn label(&self, tile: Tile) -> String {
+        if tile.is_empty() {
+            String::new()
+        } else if tile.is_blank() && tile.byte() == b'*' {
+            "?".to_string()
+        } else if tile.is_blank() {
+            self.byte_label(tile.byte()).to_lowercase()
+        } else {
+            self.byte_label(tile.byte()).to_string()
+        }
+    }
+
+    // full bag counts indexed like Tile::to_index, blanks first
+    pub fn distribution(&self) -> [u8; MAX_TILE_TYPES] {
+        let mut counts = [0; MAX_TILE_TYPES];
+        counts[0] = self.blanks;
+        for (i, letter) in self.letters.iter().enumerate() {
+            counts[i + 1] = letter.count;
+        }
+        counts
+    }
+
+    // longest label starting at chars[start]: (machine letter, label length, written in lowercase)
+    pub fn match_letter(&self, chars: &[char], start: usize) -> Option<(u8, usize, bool)> {
+        for (index, label) in &self.labels_by_length {
+            let end = start + label.len();
+            if end > chars.len() {
+                continue;
+            }
+            let matches = chars[start..end]
+                .iter()
+                .zip(label)
+                .all(|(&c, &l)| c.to_uppercase().eq(std::iter::once(l)) || c == l);
+            if matches {
+                return Some((self.letter_byte(*index), label.len(), chars[start].is_lowercase()));
+            }
+        }
+        None
+    }
+
+    // word text to machine letters, case insensitive. None if some part isn't a letter
+    pub fn encode(&self, word: &str) -> Option<Vec<u8>> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut bytes = Vec::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let (byte, len, _) = self.match_letter(&chars, i)?;
+            bytes.push(byte);
+            i += len;
+        }
+        Some(bytes)
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        bytes.iter().map(|&byte| self.byte_label(byte)).collect()
+    }
+
+    // rack/notation tiles: uppercase letters, lowercase for designated blanks and ? for undesignated ones
+    pub fn parse_tiles(&self, text: &str) -> Option<Vec<Tile>> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut tiles = Vec::with_capacity(chars.len());
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i] == '?' {
+                tiles.push(Tile::blank(None));
+                i += 1;
+                continue;
+            }
+            let (byte, len, lowercase) = self.match_letter(&chars, i)?;
+            tiles.push(if lowercase { Tile::blank(Some(byte)) } else { Tile::letter(byte) });
+            i += len;
+        }
+        Some(tiles)
+    }
+
+    // "name <name>" then one "<label> <points> <count>" line per letter, "? 0 <count>" for the blanks
+    pub fn to_text(&self) -> String {
+        let mut text = format!("name {}\n", self.name);
+        for letter in &self.letters {
+            text.push_str(&format!("{} {} {}\n", letter.label, letter.points, letter.count));
+        }
+        text.push_str(&format!("? 0 {}\n", self.blanks));
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut name = String::new();
+        let mut letters = Vec::new();
+        let mut blanks = 0;
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(rest) = line.strip_prefix("name ") {
+                name = rest.trim().to_string();
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [label, points, count] = parts[..] else {
+                return Err(format!("bad alphabet line {:?}", line));
+            };
+            let points = points.parse().map_err(|_| format!("bad points in {:?}", line))?;
+            let count = count.parse().map_err(|_| format!("bad count in {:?}", line))?;
+            if label == "?" {
+                blanks = count;
+            } else {
+                letters.push(Letter::new(label, points, count));
+            }
+        }
+        Alphabet::new(&name, letters, blanks)
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::english()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_letter_tiles_round_trip() {
+        let spanish = Alphabet::spanish();
+        // longest label first: CH, O, RR, O and not C, H, O, R, R, O
+        let chorro = spanish.encode("chorro").unwrap();
+        assert_eq!(chorro.len(), 4);
+        assert_eq!(spanish.decode(&chorro), "CHORRO");
+        assert_eq!(spanish.decode(&spanish.encode("LLAMA").unwrap()), "LLAMA");
+        assert_eq!(spanish.decode(&spanish.encode("año").unwrap()), "AÑO");
+        assert_eq!(spanish.encode("CARRO").unwrap().len(), 4);
+        assert!(spanish.encode("ÚTIL").is_none());
+        assert!(spanish.encode("KIWI").is_none());
+
+        // uppercase letters, lowercase designated blanks, ? for undesignated ones
+        let tiles = spanish.parse_tiles("CHll?Ñ").unwrap();
+        assert_eq!(tiles.len(), 4);
+        assert!(!tiles[0].is_blank() && tiles[1].is_blank() && tiles[2].is_blank());
+        let labels: String = tiles.iter().map(|&tile| spanish.label(tile)).collect();
+        assert_eq!(labels, "CHll?Ñ");
+        assert_eq!(spanish.points(tiles[0]), 5);
+        assert_eq!(spanish.points(tiles[1]), 0);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        for alphabet in [Alphabet::english(), Alphabet::spanish(), Alphabet::german(), Alphabet::polish()] {
+            assert_eq!(Alphabet::from_text(&alphabet.to_text()).unwrap(), alphabet);
+        }
+        assert!(Alphabet::from_text("A 1").is_err());
+        assert!(Alphabet::from_text("A 1 2\nA 1 2").is_err());
+    }
+}
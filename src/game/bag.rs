@@ -1,35 +1,49 @@
-use super::{rack::Rack, tile::Tile};
+use super::{
+    alphabet::{Alphabet, MAX_TILE_TYPES},
+    rack::Rack,
+    tile::Tile,
+};
 use rand::Rng;
 
+// exchanges are only allowed while the bag holds at least this many tiles
+pub const MIN_BAG_FOR_EXCHANGE: usize = 7;
+
 #[derive(Debug, Clone)]
 pub struct Bag {
-    pub tiles: [u8; 27],
+    pub tiles: [u8; MAX_TILE_TYPES], // indexed by Tile::to_index, blanks at 0
+    tile_types: u8,                  // letters in the alphabet + the blank
 }
 
 impl Bag {
-    pub fn new() -> Self {
-        // todo put this somewhere
-        let tiles = [9, 2, 2, 4, 12, 2, 3, 2, 9, 1, 1, 4, 2, 6, 8, 2, 1, 6, 4, 6, 4, 2, 2, 1, 2, 1, 2];
-        Bag { tiles }
+    pub fn new(alphabet: &Alphabet) -> Self {
+        Bag {
+            tiles: alphabet.distribution(),
+            tile_types: alphabet.len() as u8 + 1,
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.tiles.iter().sum::<u8>() == 0
+        self.len() == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.iter().map(|&count| count as usize).sum()
     }
 
     pub fn draw(&mut self) -> Option<Tile> {
-        let total: u8 = self.tiles.iter().sum();
+        // counts are u8 but a custom alphabet's bag can hold more than 255 tiles in all
+        let total = self.len();
         if total == 0 {
             return None;
         }
 
         let mut target = rand::rng().random_range(0..total);
         for (idx, &count) in self.tiles.iter().enumerate() {
-            if target < count {
+            if target < count as usize {
                 self.tiles[idx] -= 1;
-                return Some(if idx == 26 { Tile::blank(None) } else { Tile::letter(b'A' + idx as u8) });
+                return Some(Tile::from_index(idx as u8));
             }
-            target -= count;
+            target -= count as usize;
         }
         unreachable!()
     }
@@ -39,8 +53,7 @@ impl Bag {
     }
 
     pub fn swap(&mut self, rack: &mut Rack, tiles_to_swap: Vec<Tile>) -> bool {
-        let total: u8 = self.tiles.iter().sum();
-        if total < 7 || tiles_to_swap.is_empty() || tiles_to_swap.len() > 7 {
+        if self.len() < MIN_BAG_FOR_EXCHANGE || tiles_to_swap.is_empty() || tiles_to_swap.len() > 7 {
             return false;
         }
 
@@ -62,13 +75,10 @@ impl Bag {
     }
 
     pub fn get_tile_counts(&self) -> Vec<(Tile, usize)> {
-        self.tiles
+        self.tiles[..self.tile_types as usize]
             .iter()
             .enumerate()
-            .map(|(idx, &count)| {
-                let tile = if idx == 26 { Tile::blank(None) } else { Tile::letter(b'A' + idx as u8) };
-                (tile, count as usize)
-            })
+            .map(|(idx, &count)| (Tile::from_index(idx as u8), count as usize))
             .collect()
     }
 
@@ -76,3 +86,23 @@ impl Bag {
         self.tiles[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_more_than_255_tiles() {
+        let alphabet = Alphabet::from_text("name big\nA 1 200\nB 1 100\n? 0 2\n").unwrap();
+        let mut bag = Bag::new(&alphabet);
+        assert_eq!(bag.len(), 302);
+        assert!(!bag.is_empty());
+
+        let drawn = bag.draw_tiles(400);
+        assert_eq!(drawn.len(), 302);
+        assert_eq!(drawn.iter().filter(|tile| tile.is_blank()).count(), 2);
+        assert_eq!(drawn.iter().filter(|&&tile| tile == Tile::letter(b'A')).count(), 200);
+        assert!(bag.is_empty());
+        assert!(bag.draw().is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use super::tile::Tile;
-use crate::Pos;
+use crate::{Direction, Pos};
 use lazy_static::lazy_static;
 
 lazy_static! {
@@ -17,15 +17,41 @@ pub enum Multiplier {
     TripleWord,
 }
 
+const FULL_LINE: u16 = (1 << BOARD_SIZE) - 1;
+
+// indices of the set bits of a row/column mask, lowest first
+pub fn mask_bits(mut mask: u16) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let bit = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(bit)
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct Board {
     tiles: [[Option<Tile>; BOARD_SIZE]; BOARD_SIZE],
+    rows: [u16; BOARD_SIZE], // occupied squares, a bit per column
+    cols: [u16; BOARD_SIZE], // occupied squares, a bit per row
+    tile_count: u8,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
         Self {
             tiles: [[None; BOARD_SIZE]; BOARD_SIZE],
+            rows: [0; BOARD_SIZE],
+            cols: [0; BOARD_SIZE],
+            tile_count: 0,
         }
     }
 
@@ -33,12 +59,49 @@ impl Board {
         let (row, col) = (pos.row, pos.col);
         if row < BOARD_SIZE && col < BOARD_SIZE && self.tiles[row][col].is_none() {
             self.tiles[row][col] = Some(tile);
+            self.rows[row] |= 1 << col;
+            self.cols[col] |= 1 << row;
+            self.tile_count += 1;
             true
         } else {
             false
         }
     }
 
+    pub fn is_occupied(&self, pos: Pos) -> bool {
+        pos.row < BOARD_SIZE && pos.col < BOARD_SIZE && self.rows[pos.row] & (1 << pos.col) != 0
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tile_count as usize
+    }
+
+    // occupied squares of a row, bit n is column n
+    pub fn row_mask(&self, row: usize) -> u16 {
+        self.rows[row]
+    }
+
+    // occupied squares of a column, bit n is row n
+    pub fn col_mask(&self, col: usize) -> u16 {
+        self.cols[col]
+    }
+
+    // empty squares of a row with a tile above, below, left or right of them
+    pub fn neighbor_mask(&self, row: usize) -> u16 {
+        let occupied = self.rows[row];
+        let above = if row > 0 { self.rows[row - 1] } else { 0 };
+        let below = if row + 1 < BOARD_SIZE { self.rows[row + 1] } else { 0 };
+        (above | below | occupied << 1 | occupied >> 1) & !occupied & FULL_LINE
+    }
+
+    // squares a move has to cover one of: next to a tile, or the centre on an empty board
+    pub fn anchor_mask(&self, row: usize) -> u16 {
+        if self.is_empty() {
+            return if row == BOARD_SIZE / 2 { 1 << (BOARD_SIZE / 2) } else { 0 };
+        }
+        self.neighbor_mask(row)
+    }
+
     pub fn get_board_tile(&self, pos: Pos) -> Option<Tile> {
         if pos.row < BOARD_SIZE && pos.col < BOARD_SIZE {
             self.tiles[pos.row][pos.col]
@@ -55,27 +118,39 @@ impl Board {
     }
 
     pub fn is_empty(&self) -> bool {
-        for row in &self.tiles {
-            for &cell in row {
-                if cell.is_some() {
-                    return false;
-                }
+        self.tile_count == 0
+    }
+
+    // the run of tiles through pos in the given direction, empty if pos itself is empty
+    pub fn word_at(&self, pos: Pos, direction: Direction) -> Vec<(Pos, Tile)> {
+        let (d_row, d_col) = match direction {
+            Direction::Horizontal => (0, 1),
+            Direction::Vertical => (1, 0),
+        };
+
+        let mut start = pos;
+        while let Some(prev_pos) = start.offset(-d_row, -d_col) {
+            if self.get_board_tile(prev_pos).is_none() {
+                break;
             }
+            start = prev_pos;
         }
-        true
-    }
 
-    // gets filled tiles
-    pub fn tiles(&self) -> Vec<(Pos, Tile)> {
-        let mut tiles = Vec::new();
-        for row in 0..BOARD_SIZE {
-            for col in 0..BOARD_SIZE {
-                if let Some(tile) = self.tiles[row][col] {
-                    tiles.push((Pos::new(row, col), tile));
-                }
+        let mut word = Vec::new();
+        let mut current_pos = Some(start);
+        while let Some(pos) = current_pos {
+            match self.get_board_tile(pos) {
+                Some(tile) => word.push((pos, tile)),
+                None => break,
             }
+            current_pos = pos.offset(d_row, d_col);
         }
-        tiles
+        word
+    }
+
+    // gets filled tiles, row by row, straight from the occupancy bits
+    pub fn tiles(&self) -> impl Iterator<Item = (Pos, Tile)> + '_ {
+        (0..BOARD_SIZE).flat_map(move |row| mask_bits(self.rows[row]).map(move |col| (Pos::new(row, col), self.tiles[row][col].unwrap())))
     }
 }
 
@@ -1,25 +1,38 @@
+pub mod alphabet;
 pub mod bag;
 pub mod board;
 pub mod rack;
 pub mod tile;
 
 use self::{bag::Bag, board::Board, rack::Rack, tile::Tile};
-use crate::engine::moves::{Move, PlayedTile};
+use crate::{
+    Pos,
+    engine::{
+        actions::Action,
+        anchors::AnchoredBoard,
+        gaddag::Gaddag,
+        leaves::{LeaveTable, RankedMove},
+        moves::{Move, MoveGenerator, PlayedTile},
+        validate::{PlacementError, validate_placement},
+    },
+};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct Game {
-    pub board: Board,
+    pub board: AnchoredBoard, // derefs to the Board
     pub bag: Bag,
     pub racks: [Rack; 2],
     pub scores: [u16; 2],
     pub current_player: usize,
     pub zeroed_turns: u8,
+    pub gaddag: Arc<Gaddag>,
 }
 
 impl Game {
-    pub fn init() -> Self {
-        let board = Board::new();
-        let mut bag = Bag::new();
+    pub fn init(gaddag: Arc<Gaddag>) -> Self {
+        let board = AnchoredBoard::new(Board::new(), gaddag.clone());
+        let mut bag = Bag::new(gaddag.alphabet());
         let racks = [Rack::new(bag.draw_tiles(7)), Rack::new(bag.draw_tiles(7))];
         Game {
             board,
@@ -28,9 +41,52 @@ impl Game {
             current_player: 0,
             bag,
             zeroed_turns: 0,
+            gaddag,
         }
     }
 
+    // all placements for the player to move
+    pub fn generate_moves(&self) -> Vec<Move> {
+        let generator = MoveGenerator {
+            board: self.board.clone(),
+            rack: self.racks[self.current_player].clone(),
+        };
+        generator.generate_moves()
+    }
+
+    // all placements for the player to move, best equity first
+    pub fn ranked_moves(&self, leaves: &LeaveTable) -> Vec<RankedMove> {
+        let generator = MoveGenerator {
+            board: self.board.clone(),
+            rack: self.racks[self.current_player].clone(),
+        };
+        generator.ranked_moves(leaves)
+    }
+
+    // every placement, exchange and pass for the player to move, nothing once the game is over
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        let generator = MoveGenerator {
+            board: self.board.clone(),
+            rack: self.racks[self.current_player].clone(),
+        };
+        generator.generate_actions(&self.bag)
+    }
+
+    // checks tiles put down by hand against the current player's rack and the lexicon
+    pub fn validate_placement(&self, placements: &[(Pos, Tile)]) -> Result<Move, PlacementError> {
+        validate_placement(&self.board, &self.racks[self.current_player], placements, &self.gaddag)
+    }
+
+    // plays hand placed tiles only if they make a legal move, unlike play_move
+    pub fn play_placement(&mut self, placements: &[(Pos, Tile)]) -> Result<Move, PlacementError> {
+        let mv = self.validate_placement(placements)?;
+        self.play_move(&mv);
+        Ok(mv)
+    }
+
     pub fn is_over(&self) -> bool {
         self.zeroed_turns >= 6 || (self.bag.is_empty() && (self.racks[0].is_empty() || self.racks[1].is_empty()))
     }
@@ -38,14 +94,14 @@ impl Game {
     fn next_turn(&mut self) {
         self.current_player = (self.current_player + 1) % 2;
         if self.is_over() {
-            let (winner, scores) = self.end_game();
-            println!("Game over! Winner: {:?}, Scores: {:?}", winner, scores);
+            self.end_game();
         }
     }
 
     pub fn end_game(&mut self) -> (Option<usize>, [u16; 2]) {
-        let p1_rack_points: u16 = self.racks[0].tiles().iter().map(|t| t.points() as u16).sum();
-        let p2_rack_points: u16 = self.racks[1].tiles().iter().map(|t| t.points() as u16).sum();
+        let alphabet = self.gaddag.alphabet();
+        let p1_rack_points: u16 = self.racks[0].tiles().iter().map(|&t| alphabet.points(t) as u16).sum();
+        let p2_rack_points: u16 = self.racks[1].tiles().iter().map(|&t| alphabet.points(t) as u16).sum();
 
         // player 1 went out
         if self.racks[0].is_empty() && self.bag.is_empty() {
@@ -57,8 +113,8 @@ impl Game {
 
         // nobody went out
         } else {
-            self.scores[0] -= p1_rack_points;
-            self.scores[1] -= p2_rack_points;
+            self.scores[0] = self.scores[0].saturating_sub(p1_rack_points);
+            self.scores[1] = self.scores[1].saturating_sub(p2_rack_points);
         }
 
         (
@@ -75,13 +131,15 @@ impl Game {
 
     fn place_move(&mut self, mv: &Move) {
         let mut tiles_from_rack = Vec::new();
+        let mut placed = Vec::new();
 
         for (pos, played_tile) in mv.tile_positions() {
             if let PlayedTile::Rack(tile) = played_tile {
-                self.board.place_tile(pos, tile);
+                placed.push((pos, tile));
                 tiles_from_rack.push(tile);
             }
         }
+        self.board.place_tiles(placed);
 
         // Remove tiles from rack
         for tile in tiles_from_rack {
@@ -120,6 +178,14 @@ impl Game {
         }
     }
 
+    pub fn play_action(&mut self, action: &Action) {
+        match action {
+            Action::Move(mv) => self.play_move(mv),
+            Action::Swap(tiles) => self.exchange(tiles.clone()),
+            Action::Pass => self.pass_turn(),
+        }
+    }
+
     // simulation helpers
     // gives a copy of the current game state with the move applied
     pub fn simulate_move(&self, mv: &Move) -> Game {
@@ -134,3 +200,31 @@ impl Game {
         simulated
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::alphabet::Alphabet;
+
+    fn game(racks: [&[u8]; 2], scores: [u16; 2]) -> Game {
+        let gaddag = Gaddag::from_bytes(b"AA", Alphabet::english()).unwrap();
+        let mut game = Game::init(Arc::new(gaddag));
+        game.racks = racks.map(|letters| Rack::new(letters.iter().map(|&letter| Tile::letter(letter)).collect()));
+        game.scores = scores;
+        game
+    }
+
+    #[test]
+    fn rack_penalties_stop_at_zero() {
+        // nobody went out, the bag still has tiles. 5 points can't pay for a Q and a Z
+        let mut game = game([b"QZ", b"A"], [5, 30]);
+        assert_eq!(game.end_game(), (Some(1), [0, 29]));
+    }
+
+    #[test]
+    fn going_out_gets_twice_the_other_rack() {
+        let mut game = game([b"", b"QA"], [10, 30]);
+        game.bag.tiles = [0; alphabet::MAX_TILE_TYPES];
+        assert_eq!(game.end_game(), (Some(0), [32, 30]));
+    }
+}
@@ -1,12 +1,21 @@
-use super::tile::Tile;
+use super::{alphabet::MAX_LETTERS, tile::Tile};
 
 pub const RACK_TILES: usize = 7;
 
+// bit for a machine letter in the rack/cross check masks
+#[inline]
+fn letter_bit(letter: u8) -> Option<u64> {
+    match letter.checked_sub(b'A') {
+        Some(index) if (index as usize) < MAX_LETTERS => Some(1 << index),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Rack {
     pub tiles: [Tile; RACK_TILES],
     pub count: u8,
-    pub mask: u32,
+    pub mask: u64,
 }
 
 impl Rack {
@@ -14,11 +23,11 @@ impl Rack {
         let count = tile_vec.len().min(RACK_TILES) as u8;
 
         let mut tiles = [Tile::empty(); RACK_TILES];
-        let mut mask = 0u32;
+        let mut mask = 0u64;
         for (i, tile) in tile_vec.into_iter().take(RACK_TILES).enumerate() {
             tiles[i] = tile;
             if !tile.is_blank() {
-                mask |= 1 << (tile.byte() - b'A');
+                mask |= letter_bit(tile.byte()).unwrap_or(0);
             }
         }
 
@@ -36,27 +45,34 @@ impl Rack {
         self.count == 0
     }
 
+    // the letter itself, or a blank standing for it when there's none
     pub fn take_tile(&mut self, letter: u8) -> Option<Tile> {
-        if letter >= b'A' && letter <= b'Z' {
-            let bit = 1u32 << (letter - b'A');
-            if (self.mask & bit) != 0 {
-                for i in 0..self.count as usize {
-                    if self.tiles[i].byte() == letter && !self.tiles[i].is_blank() {
-                        let tile = self.tiles[i];
-                        self.remove_at(i);
-                        return Some(tile);
-                    }
+        self.take_letter(letter).or_else(|| self.take_blank(letter))
+    }
+
+    pub fn take_letter(&mut self, letter: u8) -> Option<Tile> {
+        if let Some(bit) = letter_bit(letter)
+            && (self.mask & bit) != 0
+        {
+            for i in 0..self.count as usize {
+                if self.tiles[i].byte() == letter && !self.tiles[i].is_blank() {
+                    let tile = self.tiles[i];
+                    self.remove_at(i);
+                    return Some(tile);
                 }
             }
         }
+        None
+    }
 
+    // an undesignated blank, designated as `letter`
+    pub fn take_blank(&mut self, letter: u8) -> Option<Tile> {
         for i in 0..self.count as usize {
             if self.tiles[i].is_blank() && self.tiles[i].byte() == b'*' {
                 self.remove_at(i);
                 return Some(Tile::blank(Some(letter)));
             }
         }
-
         None
     }
 
@@ -65,7 +81,7 @@ impl Rack {
             let rack_tile = if tile.is_blank() {
                 Tile::blank(None)
             } else {
-                self.mask |= 1 << (tile.byte() - b'A');
+                self.mask |= letter_bit(tile.byte()).unwrap_or(0);
                 tile
             };
             self.tiles[self.count as usize] = rack_tile;
@@ -83,12 +99,10 @@ impl Rack {
                 }
             }
         } else {
-            let letter = tile.byte();
-            if letter >= b'A' && letter <= b'Z' {
-                let bit = 1u32 << (letter - b'A');
-                if (self.mask & bit) == 0 {
-                    return false;
-                }
+            if let Some(bit) = letter_bit(tile.byte())
+                && (self.mask & bit) == 0
+            {
+                return false;
             }
 
             for i in 0..count {
@@ -104,7 +118,7 @@ impl Rack {
     fn remove_at(&mut self, index: usize) {
         let last_idx = (self.count - 1) as usize;
         let removed = self.tiles[index];
-        if !removed.is_blank() && removed.byte() >= b'A' && removed.byte() <= b'Z' {
+        if let Some(bit) = letter_bit(removed.byte()).filter(|_| !removed.is_blank()) {
             let letter = removed.byte();
             let mut found_another = false;
             for i in 0..self.count as usize {
@@ -114,7 +128,7 @@ impl Rack {
                 }
             }
             if !found_another {
-                self.mask &= !(1 << (letter - b'A'));
+                self.mask &= !bit;
             }
         }
 
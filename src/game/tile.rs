@@ -1,10 +1,11 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Tile(u8);
 
+// letters are machine letters b'A' + alphabet index, see Alphabet for their labels and points
 impl Tile {
     const EMPTY: u8 = 0;
     const BLANK_BIT: u8 = 0x80; // 10000000
-    const LETTER_MASK: u8 = 0x1F; // 00011111
+    const LETTER_MASK: u8 = 0x3F; // 00111111
 
     pub fn empty() -> Self {
         Self(Self::EMPTY)
@@ -21,6 +22,15 @@ impl Tile {
         }
     }
 
+    // inverse of to_index
+    pub fn from_index(index: u8) -> Self {
+        if index == 0 {
+            Self::blank(None)
+        } else {
+            Self(index & Self::LETTER_MASK)
+        }
+    }
+
     pub fn is_empty(self) -> bool {
         self.0 == Self::EMPTY
     }
@@ -44,32 +54,11 @@ impl Tile {
         }
     }
 
-    pub fn to_char(self) -> char {
-        self.byte() as char
-    }
-
-    pub fn points(self) -> u8 {
-        if self.is_empty() || self.is_blank() {
-            0
-        } else {
-            let letter = self.byte();
-            match letter {
-                b'A' | b'E' | b'I' | b'L' | b'N' | b'O' | b'R' | b'S' | b'T' | b'U' => 1,
-                b'D' | b'G' => 2,
-                b'B' | b'C' | b'M' | b'P' => 3,
-                b'F' | b'H' | b'V' | b'W' | b'Y' => 4,
-                b'K' => 5,
-                b'J' | b'X' => 8,
-                b'Q' | b'Z' => 10,
-                _ => 0,
-            }
-        }
-    }
-
+    // 0 for blanks, alphabet index + 1 for letters. used to index bag/rack counts
     pub fn to_index(&self) -> u8 {
-        if self.is_empty() {
+        if self.is_empty() || self.is_blank() {
             return 0;
         }
-        if self.is_blank() { 26 } else { self.0 & Self::LETTER_MASK }
+        self.0 & Self::LETTER_MASK
     }
 }
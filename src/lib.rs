@@ -2,14 +2,15 @@ pub mod ai;
 pub mod engine;
 pub mod game;
 
-pub use engine::gaddag::GADDAG;
+pub use engine::gaddag::Gaddag;
+pub use engine::lexicon::Lexicons;
 pub use engine::moves::MoveGenerator;
 pub use game::Game;
 pub use game::board::BOARD_SIZE;
 pub use game::tile::Tile;
 // use crate::ai::training::{get_best_move, setup_training_data};
 
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub struct Pos {
     pub row: usize,
     pub col: usize,
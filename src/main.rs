@@ -1,23 +1,52 @@
+#[cfg(feature = "gui")]
+#[allow(unused_imports)]
 use macroquad::prelude::*;
-use scrab::{
-    Game, Tile,
-    ai::{network::Network, training::train},
+#[cfg(feature = "ml")]
+use scrab::ai::{
+    network::{Network, default_device},
+    training::train,
 };
-
+// the ui loop below, commented out for now. until it's back the ui goes unused
+#[cfg(feature = "gui")]
+#[allow(unused_imports)]
+use scrab::{Game, Lexicons, Tile};
+
+mod cli;
+#[cfg(feature = "gui")]
+#[allow(dead_code)]
 mod ui;
+#[cfg(feature = "gui")]
+#[allow(unused_imports)]
 use ui::*;
 
 // #[macroquad::main(get_window_config)]
 fn main() {
-    // let record = scrab::ai::data::test();
-
-    let mut network = Network::load("models/model.safetensors").unwrap();
-    train(&mut network, "data/positions.bin").unwrap();
-
-    // let mut game = Game::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::run(&args) {
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        Some(Ok(())) => {}
+        // no subcommand, train
+        None => {
+            // let record = scrab::ai::data::test();
+
+            #[cfg(feature = "ml")]
+            {
+                let mut network = Network::load("models/model.safetensors", default_device()).unwrap();
+                train(&mut network, "data/positions.bin").unwrap();
+            }
+        }
+    }
+
+    // let mut lexicons = Lexicons::default();
+    // let gaddag = lexicons.load("CSW24").unwrap();
+    // let leaves = scrab::engine::leaves::LeaveTable::load("leaves/CSW24.klv", gaddag.alphabet()).unwrap_or_default();
+    // let mut game = Game::init(gaddag.clone());
     // let mut moves = Vec::new();
 
-    // let mut ui = UI::new().await;
+    // let mut ui = UI::new(gaddag.alphabet().clone()).await;
     // let mut board_updated = true;
     // let mut selected_rack_tiles: Vec<usize> = Vec::new();
 
@@ -26,7 +55,7 @@ fn main() {
     // loop {
     //     if board_updated {
     //         let timer = std::time::Instant::now();
-    //         moves = scrab::MoveGenerator::run(game.board.clone(), game.racks[game.current_player].clone());
+    //         moves = game.ranked_moves(&leaves);
     //         let elapsed = timer.elapsed();
     //         println!("Generated {} moves in {:.2?}", moves.len(), elapsed);
     //         selected_rack_tiles.clear();
@@ -43,14 +72,14 @@ fn main() {
     //     if let Some(move_idx) = ui.draw_move_list(&moves) {
     //         if let Some(selected_move) = moves.get(move_idx) {
     //             if !game.is_over() {
-    //                 game.play_move(selected_move);
+    //                 game.play_move(&selected_move.mv);
     //                 board_updated = true;
     //             }
     //         }
     //     }
 
     //     if is_key_pressed(KeyCode::R) {
-    //         game = Game::init();
+    //         game = Game::init(gaddag.clone());
     //         board_updated = true;
     //     }
 
@@ -89,6 +118,7 @@ fn main() {
     //     //     }
     //     // }
 
+    //     ui.draw_word_info(&game.board, &game.gaddag);
     //     next_frame().await
     // }
 }
@@ -1,12 +1,13 @@
 use super::MARGIN;
 use macroquad::prelude::*;
-use scrab::Pos;
+use scrab::engine::gaddag::Gaddag;
 use scrab::game::{
     bag::Bag,
     board::{BOARD_SIZE, Board, Multiplier},
     rack::Rack,
     tile::Tile,
 };
+use scrab::{Direction, Pos};
 
 pub const BOARD_SIZE_PX: f32 = 600.0;
 const BOARD_PADDING: f32 = 10.0;
@@ -26,6 +27,9 @@ const PLACEABLE_TILE_BG: Color = Color::new(0.996, 0.855, 0.624, 1.0); // #feda9
 const PLACEABLE_TILE_BORDER: Color = Color::new(0.929, 0.784, 0.537, 1.0); // #edc889
 const START_TILE_COLOR: Color = Color::new(1.0, 0.84, 0.0, 1.0); // gold
 const HIGHLIGHTED_TILE_BG: Color = Color::new(1.0, 0.8, 0.8, 1.0);
+const WORD_INFO_BG: Color = Color::new(0.0, 0.0, 0.0, 0.85);
+const WORD_INFO_LINE_HEIGHT: f32 = 18.0;
+const WORD_INFO_MAX_WORDS: usize = 8;
 
 impl super::UI {
     // returns the top left corner of a tile
@@ -99,11 +103,11 @@ impl super::UI {
         let padding = size * 0.05;
         self.draw_rounded_tile(x + padding, y + padding, size - 2.0 * padding, CORNER_RADIUS, bg_color);
 
-        let letter = tile.to_char().to_string();
+        let letter = self.alphabet.label(tile);
         let font_size = size * 0.6;
         self.draw_centered_text(&letter, x, y, size, font_size, BLACK);
 
-        let points = tile.points().to_string();
+        let points = self.alphabet.points(tile).to_string();
         if points != "0" {
             let small_font_size = size * 0.25;
             let points_x = x + size - size * 0.25;
@@ -244,4 +248,86 @@ impl super::UI {
             }
         }
     }
+
+    // board cell under the mouse, if any
+    fn hovered_pos(&self) -> Option<Pos> {
+        let (mouse_x, mouse_y) = mouse_position();
+        if mouse_x < MARGIN || mouse_y < MARGIN {
+            return None;
+        }
+        let col = ((mouse_x - MARGIN) / CELL_SIZE) as usize;
+        let row = ((mouse_y - MARGIN) / CELL_SIZE) as usize;
+        if row < BOARD_SIZE && col < BOARD_SIZE {
+            Some(Pos::new(row, col))
+        } else {
+            None
+        }
+    }
+
+    // hooks and extensions of the word under the mouse
+    pub fn draw_word_info(&mut self, board: &Board, gaddag: &Gaddag) {
+        let Some(pos) = self.hovered_pos() else {
+            return;
+        };
+
+        // prefer the horizontal word, fall back to the vertical one for lone letters
+        let mut tiles = board.word_at(pos, Direction::Horizontal);
+        if tiles.len() < 2 {
+            tiles = board.word_at(pos, Direction::Vertical);
+        }
+        if tiles.len() < 2 {
+            return;
+        }
+
+        let word: String = tiles.iter().map(|(_, tile)| self.alphabet.byte_label(tile.byte())).collect();
+        if self.word_info.as_ref().map(|(hovered, _)| hovered != &word).unwrap_or(true) {
+            self.word_info = Some((word.clone(), gaddag.extensions(&word)));
+        }
+        let Some((_, extensions)) = &self.word_info else {
+            return;
+        };
+
+        let hooks = |letters: &[String]| if letters.is_empty() { "-".to_string() } else { letters.concat() };
+        let list = |words: &[String]| {
+            let mut text = words.iter().take(WORD_INFO_MAX_WORDS).cloned().collect::<Vec<_>>().join(" ");
+            if words.len() > WORD_INFO_MAX_WORDS {
+                text.push_str(&format!(" (+{})", words.len() - WORD_INFO_MAX_WORDS));
+            }
+            text
+        };
+
+        let mut lines = vec![format!("{} {} {}", hooks(&extensions.front_hooks), word, hooks(&extensions.back_hooks))];
+        if !extensions.front.is_empty() {
+            lines.push(format!("front: {}", list(&extensions.front)));
+        }
+        if !extensions.back.is_empty() {
+            lines.push(format!("back: {}", list(&extensions.back)));
+        }
+        if !extensions.inner.is_empty() {
+            lines.push(format!("both: {}", list(&extensions.inner)));
+        }
+
+        let width = lines
+            .iter()
+            .map(|line| measure_text(line, self.font.as_ref(), 14, 1.0).width)
+            .fold(0.0, f32::max);
+        let (mouse_x, mouse_y) = mouse_position();
+        let x = mouse_x + 16.0;
+        let y = mouse_y + 16.0;
+        draw_rectangle(x, y, width + 16.0, lines.len() as f32 * WORD_INFO_LINE_HEIGHT + 8.0, WORD_INFO_BG);
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text_ex(
+                line,
+                x + 8.0,
+                y + (i + 1) as f32 * WORD_INFO_LINE_HEIGHT,
+                TextParams {
+                    font: self.font.as_ref(),
+                    font_size: 14,
+                    color: WHITE,
+                    ..Default::default()
+                },
+            );
+        }
+    }
 }
@@ -5,7 +5,10 @@ use super::{
 use macroquad::prelude::*;
 use scrab::{
     Direction,
-    engine::moves::{Move, PlayedTile},
+    engine::{
+        leaves::RankedMove,
+        moves::{Move, PlayedTile},
+    },
 };
 
 const PLAYER_HEADER_HEIGHT: f32 = 40.0;
@@ -40,9 +43,9 @@ impl super::UI {
         }
     }
 
-    pub fn draw_move_list(&mut self, moves: &[Move]) -> Option<usize> {
-        let mut moves: Vec<_> = moves.iter().enumerate().collect();
-        moves.sort_by(|a, b| b.1.score.cmp(&a.1.score));
+    // moves come in ranked by equity (see Game::ranked_moves), raw score alone undervalues keeping good tiles
+    pub fn draw_move_list(&mut self, moves: &[RankedMove]) -> Option<usize> {
+        let moves: Vec<_> = moves.iter().enumerate().collect();
 
         let (_, scroll) = mouse_wheel();
         if scroll != 0.0 {
@@ -86,7 +89,8 @@ impl super::UI {
         let mut clicked_move = None;
 
         let end_idx = (self.scroll_offset + VISIBLE_MOVES).min(moves.len());
-        for (display_idx, (original_idx, mv)) in moves[self.scroll_offset..end_idx].iter().enumerate() {
+        for (display_idx, (original_idx, ranked)) in moves[self.scroll_offset..end_idx].iter().enumerate() {
+            let mv = &ranked.mv;
             let actual_idx = self.scroll_offset + display_idx;
             let text_y = MARGIN + MOVES_HEADER_HEIGHT + PLAYER_HEADER_HEIGHT + 10.0 + display_idx as f32 * MOVE_LIST_LINE_HEIGHT;
             let hover_rect = (SIDEBAR_X, text_y - 10.0, SIDEBAR_WIDTH - 20.0, MOVE_LIST_LINE_HEIGHT);
@@ -111,7 +115,13 @@ impl super::UI {
             };
 
             draw_text_ex(
-                &format!("{} {} ({}pts)", mv.get_word_string(), direction_arrow, mv.score),
+                &format!(
+                    "{} {} ({}pts, {:.1})",
+                    mv.get_word_string(&self.alphabet),
+                    direction_arrow,
+                    mv.score,
+                    ranked.equity
+                ),
                 SIDEBAR_X,
                 text_y + 5.,
                 TextParams {
@@ -123,10 +133,10 @@ impl super::UI {
             );
         }
 
-        if let Some(idx) = self.hovered_move {
-            if let Some((_, mv)) = moves.get(idx) {
-                self.draw_move_preview(mv);
-            }
+        if let Some(idx) = self.hovered_move
+            && let Some((_, ranked)) = moves.get(idx)
+        {
+            self.draw_move_preview(&ranked.mv);
         }
         clicked_move
     }
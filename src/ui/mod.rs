@@ -1,6 +1,8 @@
 mod board;
 mod engine;
 use macroquad::prelude::*;
+use scrab::engine::hooks::WordExtensions;
+use scrab::game::alphabet::Alphabet;
 
 const MARGIN: f32 = 50.0;
 const WINDOW_WIDTH: f32 = board::BOARD_SIZE_PX + MARGIN * 2.0 + engine::SIDEBAR_WIDTH + MARGIN;
@@ -11,6 +13,8 @@ pub struct UI {
     font: Option<Font>,
     scroll_offset: usize,
     hovered_move: Option<usize>,
+    word_info: Option<(String, WordExtensions)>,
+    alphabet: Alphabet,
 }
 
 pub fn get_window_config() -> Conf {
@@ -24,11 +28,13 @@ pub fn get_window_config() -> Conf {
 }
 
 impl UI {
-    pub async fn new() -> Self {
+    pub async fn new(alphabet: Alphabet) -> Self {
         Self {
             font: load_ttf_font_from_bytes(include_bytes!("../../outfit.ttf")).ok(),
             scroll_offset: 0,
             hovered_move: None,
+            word_info: None,
+            alphabet,
         }
     }
 }